@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use hybridmap::HybridMap;
+use hybridmap::{HybridMap, HybridSortedMap};
 use rand::{distributions::DistString, Rng};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -227,6 +227,152 @@ fn hybridmap_bench(c: &mut Criterion) {
     });
 
     group.finish();
+
+    // Read-heavy maps that stay inline.
+    let mut group = c.benchmark_group("sorted");
+    for size in [8, 16].iter() {
+        group.bench_function(format!("HybridMap {}", size), |b| {
+            b.iter(|| {
+                let mut map = HybridMap::<i64, i64, 16>::new();
+                for i in 0..*size {
+                    map.insert(rng.gen_range(0..*size), i);
+                }
+
+                let mut sum = 0_i64;
+                for _i in 0..criterion::black_box(*size * 8) {
+                    let n = map.get(&rng.gen_range(0..*size));
+                    if let Some(n) = n {
+                        sum += n;
+                    }
+                }
+            })
+        });
+        group.bench_function(format!("HybridSortedMap {}", size), |b| {
+            b.iter(|| {
+                let mut map = HybridSortedMap::<i64, i64, 16>::new();
+                for i in 0..*size {
+                    map.insert(rng.gen_range(0..*size), i);
+                }
+
+                let mut sum = 0_i64;
+                for _i in 0..criterion::black_box(*size * 8) {
+                    let n = map.get(&rng.gen_range(0..*size));
+                    if let Some(n) = n {
+                        sum += n;
+                    }
+                }
+            })
+        });
+    }
+    group.finish();
+
+    // Linear scan against binary search on the same inline map.
+    let mut group = c.benchmark_group("get_sorted");
+    for size in [4_u32, 8, 16].iter() {
+        let mut map = HybridMap::<u32, u32, 16>::new();
+        for i in 0..*size {
+            map.insert_sorted(i, i);
+        }
+        group.bench_function(format!("get {}", size), |b| {
+            b.iter(|| {
+                let mut sum = 0_u32;
+                for _i in 0..criterion::black_box(*size * 8) {
+                    if let Some(n) = map.get(&rng.gen_range(0..*size)) {
+                        sum += n;
+                    }
+                }
+                sum
+            })
+        });
+        group.bench_function(format!("get_sorted {}", size), |b| {
+            b.iter(|| {
+                let mut sum = 0_u32;
+                for _i in 0..criterion::black_box(*size * 8) {
+                    if let Some(n) = map.get_sorted(&rng.gen_range(0..*size)) {
+                        sum += n;
+                    }
+                }
+                sum
+            })
+        });
+    }
+    group.finish();
+
+    // Lookups of small Copy keys in inline maps, half of them missing.
+    let mut group = c.benchmark_group("get_copy");
+    for size in [4_u32, 8, 16].iter() {
+        let mut map_u32 = HybridMap::<u32, u32, 16>::new();
+        let mut map_u64 = HybridMap::<u64, u64, 16>::new();
+        for i in 0..*size {
+            map_u32.insert(i, i);
+            map_u64.insert(i as u64, i as u64);
+        }
+        let queries: Vec<u32> = (0..256).map(|_| rng.gen_range(0..*size * 2)).collect();
+        group.bench_function(format!("u32 {}", size), |b| {
+            b.iter(|| {
+                let mut sum = 0_u32;
+                for query in criterion::black_box(&queries) {
+                    if let Some(n) = map_u32.get(query) {
+                        sum += n;
+                    }
+                }
+                sum
+            })
+        });
+        let queries: Vec<u64> = queries.iter().map(|&query| query as u64).collect();
+        group.bench_function(format!("u64 {}", size), |b| {
+            b.iter(|| {
+                let mut sum = 0_u64;
+                for query in criterion::black_box(&queries) {
+                    if let Some(n) = map_u64.get(query) {
+                        sum += n;
+                    }
+                }
+                sum
+            })
+        });
+    }
+    group.finish();
+
+    // Inserting exactly past the threshold, to measure the switch to the HashMap.
+    // The HashMap baselines show the cost of rehashing while growing.
+    let mut group = c.benchmark_group("spill");
+    for size in [4_i64, 8, 16].iter() {
+        group.bench_function(format!("HybridMap {}", size), |b| {
+            b.iter(|| match size {
+                4 => criterion::black_box(fill_past_threshold::<4>()),
+                8 => criterion::black_box(fill_past_threshold::<8>()),
+                _ => criterion::black_box(fill_past_threshold::<16>()),
+            })
+        });
+        group.bench_function(format!("HashMap::new {}", size), |b| {
+            b.iter(|| {
+                let mut map = HashMap::<i64, i64>::new();
+                for i in 0..=*size {
+                    map.insert(i, i);
+                }
+                criterion::black_box(map.len())
+            })
+        });
+        group.bench_function(format!("HashMap::with_capacity {}", size), |b| {
+            b.iter(|| {
+                let mut map = HashMap::<i64, i64>::with_capacity(*size as usize + 1);
+                for i in 0..=*size {
+                    map.insert(i, i);
+                }
+                criterion::black_box(map.len())
+            })
+        });
+    }
+    group.finish();
+}
+
+fn fill_past_threshold<const N: usize>() -> usize {
+    let mut map = HybridMap::<i64, i64, N>::new();
+    for i in 0..=N as i64 {
+        map.insert(i, i);
+    }
+    map.len()
 }
 
 criterion_group!(benches, hybridmap_bench);
@@ -0,0 +1,1154 @@
+//! Entry API for `HybridMap`, mirroring `std::collections::hash_map::Entry`.
+use crate::{hash_map, DefaultHashBuilder, HybridMap, InnerContainer};
+#[cfg(feature = "std")]
+use core::marker::PhantomData;
+use core::{
+    borrow::Borrow,
+    error::Error,
+    fmt,
+    hash::{BuildHasher, Hash},
+};
+use smallvec::SmallVec;
+
+/// A view into a single entry in a `HybridMap`, which may either be vacant or
+/// occupied.
+///
+/// This `enum` is constructed from the [`entry`](HybridMap::entry) method on
+/// [`HybridMap`].
+pub enum Entry<'a, K, V, const N: usize, S = DefaultHashBuilder> {
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K, V, N, S>),
+    /// A vacant entry.
+    Vacant(VacantEntry<'a, K, V, N, S>),
+}
+
+/// A view into an occupied entry in a `HybridMap`.
+pub struct OccupiedEntry<'a, K, V, const N: usize, S = DefaultHashBuilder> {
+    inner: OccupiedInner<'a, K, V, N, S>,
+}
+
+enum OccupiedInner<'a, K, V, const N: usize, S> {
+    // The index of the entry in the vector, so we don't have to scan it again.
+    Vec {
+        vec: &'a mut SmallVec<(K, V), N>,
+        index: usize,
+    },
+    // Only used if removing the entry cannot get the map down to the shrink
+    // threshold, as the hash map entry cannot switch it back to the vector.
+    // Only the hashbrown entries are generic over the hasher.
+    #[cfg(feature = "std")]
+    HashMap(hash_map::OccupiedEntry<'a, K, V>, PhantomData<S>),
+    #[cfg(not(feature = "std"))]
+    HashMap(hash_map::OccupiedEntry<'a, K, V, S>),
+    // Removing the entry would switch the map back to the vector,
+    // so the map is kept and queried again with the key.
+    Shrinking {
+        map: &'a mut HybridMap<K, V, N, S>,
+        key: K,
+    },
+    // Found by entry_by_ref without an owned key. The standard library has
+    // no entry API for borrowed keys, so the map is queried again with it.
+    HashMapRef {
+        map: &'a mut HybridMap<K, V, N, S>,
+        key: &'a K,
+    },
+}
+
+/// A view into a vacant entry in a `HybridMap`.
+pub struct VacantEntry<'a, K, V, const N: usize, S = DefaultHashBuilder> {
+    inner: VacantInner<'a, K, V, N, S>,
+}
+
+enum VacantInner<'a, K, V, const N: usize, S> {
+    // We keep the whole map around as inserting may switch the inner container
+    // from the vector to the hash map. entry_by_ref also uses it once spilled,
+    // to hash the cloned key only when inserting.
+    Map {
+        map: &'a mut HybridMap<K, V, N, S>,
+        key: K,
+    },
+    #[cfg(feature = "std")]
+    HashMap(hash_map::VacantEntry<'a, K, V>),
+    #[cfg(not(feature = "std"))]
+    HashMap(hash_map::VacantEntry<'a, K, V, S>),
+}
+
+/// A view into a single entry in a `HybridMap`, found from a borrowed key.
+///
+/// This `enum` is constructed from the [`entry_ref`](HybridMap::entry_ref)
+/// method on [`HybridMap`]. The owned key is only created when inserting
+/// into a vacant entry.
+pub enum EntryRef<'a, K, V, Q: ?Sized, const N: usize, S = DefaultHashBuilder> {
+    /// An occupied entry.
+    Occupied(OccupiedEntryRef<'a, K, V, Q, N, S>),
+    /// A vacant entry.
+    Vacant(VacantEntryRef<'a, K, V, Q, N, S>),
+}
+
+/// A view into an occupied entry in a `HybridMap`, found from a borrowed key.
+pub struct OccupiedEntryRef<'a, K, V, Q: ?Sized, const N: usize, S = DefaultHashBuilder> {
+    inner: OccupiedRefInner<'a, K, V, Q, N, S>,
+}
+
+enum OccupiedRefInner<'a, K, V, Q: ?Sized, const N: usize, S> {
+    Vec {
+        vec: &'a mut SmallVec<(K, V), N>,
+        index: usize,
+    },
+    // Only used with hashbrown, if removing the entry cannot get the map down
+    // to the shrink threshold.
+    #[cfg(not(feature = "std"))]
+    HashMap(hash_map::OccupiedEntry<'a, K, V, S>),
+    // The standard library has no entry API for borrowed keys, and removing
+    // the entry may switch the map back to the vector, so the map is kept and
+    // queried again with the borrowed key.
+    Map {
+        map: &'a mut HybridMap<K, V, N, S>,
+        key: &'a Q,
+    },
+}
+
+/// A view into a vacant entry in a `HybridMap`, found from a borrowed key.
+pub struct VacantEntryRef<'a, K, V, Q: ?Sized, const N: usize, S = DefaultHashBuilder> {
+    inner: VacantRefInner<'a, K, V, Q, N, S>,
+}
+
+enum VacantRefInner<'a, K, V, Q: ?Sized, const N: usize, S> {
+    // Inserting may switch the inner container from the vector to the hash map.
+    Map {
+        map: &'a mut HybridMap<K, V, N, S>,
+        key: &'a Q,
+    },
+    #[cfg(not(feature = "std"))]
+    HashMap(hash_map::VacantEntryRef<'a, 'a, K, Q, V, S>),
+}
+
+impl<K, V, const N: usize, S> HybridMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Gets the given key's corresponding entry in the map for in-place manipulation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hybridmap::HybridMap;
+    ///
+    /// let mut counters = HybridMap::<&str, i32, 8>::new();
+    /// for word in ["a", "b", "a"] {
+    ///     *counters.entry(word).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(counters.get(&"a"), Some(&2));
+    /// assert_eq!(counters.get(&"b"), Some(&1));
+    /// ```
+    #[inline]
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, N, S> {
+        match self.inner {
+            InnerContainer::Vec(ref vec, _) => match vec.iter().position(|(k, _)| k == &key) {
+                Some(index) => {
+                    let InnerContainer::Vec(vec, _) = &mut self.inner else {
+                        unreachable!()
+                    };
+                    Entry::Occupied(OccupiedEntry {
+                        inner: OccupiedInner::Vec { vec, index },
+                    })
+                }
+                None => Entry::Vacant(VacantEntry {
+                    inner: VacantInner::Map { map: self, key },
+                }),
+            },
+            InnerContainer::HashMap(ref map)
+                if self.removal_shrinks() && map.contains_key(&key) =>
+            {
+                Entry::Occupied(OccupiedEntry {
+                    inner: OccupiedInner::Shrinking { map: self, key },
+                })
+            }
+            InnerContainer::HashMap(ref mut map) => match map.entry(key) {
+                hash_map::Entry::Occupied(entry) => Entry::Occupied(OccupiedEntry {
+                    #[cfg(feature = "std")]
+                    inner: OccupiedInner::HashMap(entry, PhantomData),
+                    #[cfg(not(feature = "std"))]
+                    inner: OccupiedInner::HashMap(entry),
+                }),
+                hash_map::Entry::Vacant(entry) => Entry::Vacant(VacantEntry {
+                    inner: VacantInner::HashMap(entry),
+                }),
+            },
+        }
+    }
+
+    /// Gets the entry of a borrowed key for in-place manipulation.
+    ///
+    /// Unlike [`entry`](Self::entry), the owned key is only created from the
+    /// borrowed one when a value is inserted into a vacant entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hybridmap::HybridMap;
+    ///
+    /// let mut counters = HybridMap::<String, i32, 8>::new();
+    /// for word in ["a", "b", "a"] {
+    ///     *counters.entry_ref(word).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(counters.get("a"), Some(&2));
+    /// assert_eq!(counters.get("b"), Some(&1));
+    /// ```
+    #[inline]
+    pub fn entry_ref<'a, Q>(&'a mut self, key: &'a Q) -> EntryRef<'a, K, V, Q, N, S>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        #[cfg(not(feature = "std"))]
+        let shrinks = self.removal_shrinks();
+        match self.inner {
+            InnerContainer::Vec(ref vec, _) => {
+                match vec.iter().position(|(k, _)| k.borrow() == key) {
+                    Some(index) => {
+                        let InnerContainer::Vec(vec, _) = &mut self.inner else {
+                            unreachable!()
+                        };
+                        EntryRef::Occupied(OccupiedEntryRef {
+                            inner: OccupiedRefInner::Vec { vec, index },
+                        })
+                    }
+                    None => EntryRef::Vacant(VacantEntryRef {
+                        inner: VacantRefInner::Map { map: self, key },
+                    }),
+                }
+            }
+            #[cfg(feature = "std")]
+            InnerContainer::HashMap(_) => self.lookup_entry_ref(key),
+            #[cfg(not(feature = "std"))]
+            InnerContainer::HashMap(_) if shrinks => self.lookup_entry_ref(key),
+            #[cfg(not(feature = "std"))]
+            InnerContainer::HashMap(ref mut map) => match map.entry_ref(key) {
+                hash_map::EntryRef::Occupied(entry) => EntryRef::Occupied(OccupiedEntryRef {
+                    inner: OccupiedRefInner::HashMap(entry),
+                }),
+                hash_map::EntryRef::Vacant(entry) => EntryRef::Vacant(VacantEntryRef {
+                    inner: VacantRefInner::HashMap(entry),
+                }),
+            },
+        }
+    }
+
+    /// Gets the entry of a borrowed key in a `HashMap` that is queried again
+    /// on each access.
+    #[inline]
+    fn lookup_entry_ref<'a, Q>(&'a mut self, key: &'a Q) -> EntryRef<'a, K, V, Q, N, S>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.contains_key(key) {
+            EntryRef::Occupied(OccupiedEntryRef {
+                inner: OccupiedRefInner::Map { map: self, key },
+            })
+        } else {
+            EntryRef::Vacant(VacantEntryRef {
+                inner: VacantRefInner::Map { map: self, key },
+            })
+        }
+    }
+}
+
+impl<K, V, const N: usize, S> HybridMap<K, V, N, S>
+where
+    K: Eq + Hash + Clone,
+    S: BuildHasher + Clone,
+{
+    /// Gets the entry of a borrowed key for in-place manipulation,
+    /// cloning the key only if the entry is vacant.
+    ///
+    /// This sits between [`entry`](Self::entry), which always takes an owned
+    /// key, and [`entry_ref`](Self::entry_ref), which needs `K: From<&Q>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hybridmap::HybridMap;
+    ///
+    /// let mut counters = HybridMap::<String, i32, 8>::new();
+    /// let word = "a".to_string();
+    /// *counters.entry_by_ref(&word).or_insert(0) += 1;
+    /// *counters.entry_by_ref(&word).or_insert(0) += 1;
+    ///
+    /// assert_eq!(counters.get("a"), Some(&2));
+    /// ```
+    #[inline]
+    pub fn entry_by_ref<'a>(&'a mut self, key: &'a K) -> Entry<'a, K, V, N, S> {
+        let occupied = match self.inner {
+            InnerContainer::Vec(ref vec, _) => vec.iter().position(|(k, _)| k == key),
+            InnerContainer::HashMap(ref map) => {
+                if map.contains_key(key) {
+                    return Entry::Occupied(OccupiedEntry {
+                        inner: OccupiedInner::HashMapRef { map: self, key },
+                    });
+                }
+                None
+            }
+        };
+        // Once spilled, the cloned key is hashed again if a value is inserted.
+        let Some(index) = occupied else {
+            return Entry::Vacant(VacantEntry {
+                inner: VacantInner::Map {
+                    map: self,
+                    key: key.clone(),
+                },
+            });
+        };
+        let InnerContainer::Vec(vec, _) = &mut self.inner else {
+            unreachable!()
+        };
+        Entry::Occupied(OccupiedEntry {
+            inner: OccupiedInner::Vec { vec, index },
+        })
+    }
+}
+
+/// The error returned by [`try_insert`](HybridMap::try_insert) when the key
+/// already exists.
+///
+/// Contains the occupied entry, and the value that was not inserted.
+pub struct OccupiedError<'a, K, V, const N: usize, S = DefaultHashBuilder> {
+    /// The entry in the map that was already occupied.
+    pub entry: OccupiedEntry<'a, K, V, N, S>,
+    /// The value which was not inserted, because the entry was already occupied.
+    pub value: V,
+}
+
+impl<K, V, const N: usize, S> fmt::Debug for OccupiedError<'_, K, V, N, S>
+where
+    K: Eq + Hash + fmt::Debug,
+    V: fmt::Debug,
+    S: BuildHasher + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", self.entry.key())
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
+    }
+}
+
+impl<K, V, const N: usize, S> fmt::Display for OccupiedError<'_, K, V, N, S>
+where
+    K: Eq + Hash + fmt::Debug,
+    V: fmt::Debug,
+    S: BuildHasher + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value,
+            self.entry.key(),
+            self.entry.get(),
+        )
+    }
+}
+
+impl<K, V, const N: usize, S> Error for OccupiedError<'_, K, V, N, S>
+where
+    K: Eq + Hash + fmt::Debug,
+    V: fmt::Debug,
+    S: BuildHasher + Clone,
+{
+}
+
+impl<K, V, const N: usize, S> HybridMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Tries to insert a key-value pair into the map, and returns
+    /// a mutable reference to the value in the entry.
+    ///
+    /// If the map already had this key present, nothing is updated, and
+    /// an error containing the occupied entry and the value is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hybridmap::HybridMap;
+    ///
+    /// let mut map = HybridMap::<i32, &str, 8>::new();
+    /// assert_eq!(map.try_insert(37, "a").unwrap(), &"a");
+    ///
+    /// let err = map.try_insert(37, "b").unwrap_err();
+    /// assert_eq!(err.entry.key(), &37);
+    /// assert_eq!(err.entry.get(), &"a");
+    /// assert_eq!(err.value, "b");
+    /// ```
+    #[inline]
+    pub fn try_insert(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Result<&mut V, OccupiedError<'_, K, V, N, S>> {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value }),
+            Entry::Vacant(entry) => Ok(entry.insert(value)),
+        }
+    }
+}
+
+impl<'a, K, V, const N: usize, S> Entry<'a, K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Ensures a value is in the entry by inserting the default if empty,
+    /// and returns a mutable reference to the value in the entry.
+    #[inline]
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the default
+    /// function if empty, and returns a mutable reference to the value in the entry.
+    #[inline]
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the default
+    /// function if empty, and returns a mutable reference to the value in the entry.
+    ///
+    /// The function is given a reference to the key, for values depending on it.
+    #[inline]
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    /// Returns a reference to this entry's key.
+    #[inline]
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential inserts into the map.
+    #[inline]
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+
+    /// Like `and_modify`, with the key also given to `f`.
+    ///
+    /// The `HashMap` entries cannot lend the key and the value at the same
+    /// time, so the key is cloned once the map has switched to a `HashMap`.
+    #[inline]
+    pub fn and_modify_with_key<F: FnOnce(&K, &mut V)>(mut self, f: F) -> Self
+    where
+        K: Clone,
+    {
+        if let Entry::Occupied(entry) = &mut self {
+            match &mut entry.inner {
+                OccupiedInner::Vec { vec, index } => {
+                    let (key, value) = &mut vec[*index];
+                    f(key, value);
+                }
+                OccupiedInner::HashMap(entry, ..) => {
+                    let key = entry.key().clone();
+                    f(&key, entry.get_mut());
+                }
+                OccupiedInner::Shrinking { map, key } => {
+                    let key = map.get_key_value(&*key).unwrap().0.clone();
+                    f(&key, map.get_mut(&key).unwrap());
+                }
+                OccupiedInner::HashMapRef { map, key } => {
+                    let key = map.get_key_value(*key).unwrap().0.clone();
+                    f(&key, map.get_mut(&key).unwrap());
+                }
+            }
+        }
+        self
+    }
+}
+
+impl<'a, K, V, const N: usize, S> Entry<'a, K, V, N, S>
+where
+    K: Eq + Hash,
+    V: Default,
+    S: BuildHasher + Clone,
+{
+    /// Ensures a value is in the entry by inserting the default value if empty,
+    /// and returns a mutable reference to the value in the entry.
+    #[inline]
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'a, K, V, const N: usize, S> OccupiedEntry<'a, K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Gets a reference to the key in the entry.
+    #[inline]
+    pub fn key(&self) -> &K {
+        match &self.inner {
+            OccupiedInner::Vec { vec, index } => &vec[*index].0,
+            OccupiedInner::HashMap(entry, ..) => entry.key(),
+            OccupiedInner::Shrinking { map, key } => map.get_key_value(key).unwrap().0,
+            OccupiedInner::HashMapRef { map, key } => map.get_key_value(*key).unwrap().0,
+        }
+    }
+
+    /// Gets a reference to the value in the entry.
+    #[inline]
+    pub fn get(&self) -> &V {
+        match &self.inner {
+            OccupiedInner::Vec { vec, index } => &vec[*index].1,
+            OccupiedInner::HashMap(entry, ..) => entry.get(),
+            OccupiedInner::Shrinking { map, key } => map.get(key).unwrap(),
+            OccupiedInner::HashMapRef { map, key } => map.get(*key).unwrap(),
+        }
+    }
+
+    /// Gets a mutable reference to the value in the entry.
+    ///
+    /// If you need a reference to the `OccupiedEntry` which may outlive the
+    /// destruction of the `Entry` value, see [`into_mut`](Self::into_mut).
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut V {
+        match &mut self.inner {
+            OccupiedInner::Vec { vec, index } => &mut vec[*index].1,
+            OccupiedInner::HashMap(entry, ..) => entry.get_mut(),
+            OccupiedInner::Shrinking { map, key } => map.get_mut(key).unwrap(),
+            OccupiedInner::HashMapRef { map, key } => map.get_mut(*key).unwrap(),
+        }
+    }
+
+    /// Converts the entry into a mutable reference to the value in the entry
+    /// with a lifetime bound to the map itself.
+    #[inline]
+    pub fn into_mut(self) -> &'a mut V {
+        match self.inner {
+            OccupiedInner::Vec { vec, index } => &mut vec[index].1,
+            OccupiedInner::HashMap(entry, ..) => entry.into_mut(),
+            OccupiedInner::Shrinking { map, key } => map.get_mut(&key).unwrap(),
+            OccupiedInner::HashMapRef { map, key } => map.get_mut(key).unwrap(),
+        }
+    }
+
+    /// Sets the value of the entry, and returns the entry's old value.
+    #[inline]
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    /// Takes the value out of the entry, and returns it.
+    #[inline]
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Take the ownership of the key and value from the map.
+    #[inline]
+    pub fn remove_entry(self) -> (K, V) {
+        match self.inner {
+            OccupiedInner::Vec { vec, index } => vec.remove(index),
+            OccupiedInner::HashMap(entry, ..) => entry.remove_entry(),
+            OccupiedInner::Shrinking { map, key } => map.remove_entry(&key).unwrap(),
+            OccupiedInner::HashMapRef { map, key } => map.remove_entry(key).unwrap(),
+        }
+    }
+}
+
+impl<'a, K, V, const N: usize, S> VacantEntry<'a, K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Gets a reference to the key that would be used when inserting a value
+    /// through the `VacantEntry`.
+    #[inline]
+    pub fn key(&self) -> &K {
+        match &self.inner {
+            VacantInner::Map { key, .. } => key,
+            VacantInner::HashMap(entry) => entry.key(),
+        }
+    }
+
+    /// Take ownership of the key.
+    #[inline]
+    pub fn into_key(self) -> K {
+        match self.inner {
+            VacantInner::Map { key, .. } => key,
+            VacantInner::HashMap(entry) => entry.into_key(),
+        }
+    }
+
+    /// Sets the value of the entry with the `VacantEntry`'s key,
+    /// and returns a mutable reference to it.
+    ///
+    /// The map switches to a `HashMap` if the vector is full.
+    #[inline]
+    pub fn insert(self, value: V) -> &'a mut V {
+        match self.inner {
+            VacantInner::Map { map, key } => map.insert_unique(key, value),
+            VacantInner::HashMap(entry) => entry.insert(value),
+        }
+    }
+}
+
+impl<'a, K, V, Q, const N: usize, S> EntryRef<'a, K, V, Q, N, S>
+where
+    K: Eq + Hash + Borrow<Q> + From<&'a Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher + Clone,
+{
+    /// Ensures a value is in the entry by inserting the default if empty,
+    /// and returns a mutable reference to the value in the entry.
+    #[inline]
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the default
+    /// function if empty, and returns a mutable reference to the value in the entry.
+    #[inline]
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the default value if empty,
+    /// and returns a mutable reference to the value in the entry.
+    #[inline]
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Returns a reference to this entry's borrowed key.
+    #[inline]
+    pub fn key(&self) -> &Q {
+        match self {
+            EntryRef::Occupied(entry) => entry.key().borrow(),
+            EntryRef::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential inserts into the map.
+    #[inline]
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let EntryRef::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V, Q, const N: usize, S> OccupiedEntryRef<'a, K, V, Q, N, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher + Clone,
+{
+    /// Gets a reference to the key in the entry.
+    #[inline]
+    pub fn key(&self) -> &K {
+        match &self.inner {
+            OccupiedRefInner::Vec { vec, index } => &vec[*index].0,
+            #[cfg(not(feature = "std"))]
+            OccupiedRefInner::HashMap(entry) => entry.key(),
+            OccupiedRefInner::Map { map, key } => map.get_key_value(*key).unwrap().0,
+        }
+    }
+
+    /// Gets a reference to the value in the entry.
+    #[inline]
+    pub fn get(&self) -> &V {
+        match &self.inner {
+            OccupiedRefInner::Vec { vec, index } => &vec[*index].1,
+            #[cfg(not(feature = "std"))]
+            OccupiedRefInner::HashMap(entry) => entry.get(),
+            OccupiedRefInner::Map { map, key } => map.get(*key).unwrap(),
+        }
+    }
+
+    /// Gets a mutable reference to the value in the entry.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut V {
+        match &mut self.inner {
+            OccupiedRefInner::Vec { vec, index } => &mut vec[*index].1,
+            #[cfg(not(feature = "std"))]
+            OccupiedRefInner::HashMap(entry) => entry.get_mut(),
+            OccupiedRefInner::Map { map, key } => map.get_mut(*key).unwrap(),
+        }
+    }
+
+    /// Converts the entry into a mutable reference to the value in the entry
+    /// with a lifetime bound to the map itself.
+    #[inline]
+    pub fn into_mut(self) -> &'a mut V {
+        match self.inner {
+            OccupiedRefInner::Vec { vec, index } => &mut vec[index].1,
+            #[cfg(not(feature = "std"))]
+            OccupiedRefInner::HashMap(entry) => entry.into_mut(),
+            OccupiedRefInner::Map { map, key } => map.get_mut(key).unwrap(),
+        }
+    }
+
+    /// Sets the value of the entry, and returns the entry's old value.
+    #[inline]
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    /// Takes the value out of the entry, and returns it.
+    #[inline]
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Take the ownership of the key and value from the map.
+    #[inline]
+    pub fn remove_entry(self) -> (K, V) {
+        match self.inner {
+            OccupiedRefInner::Vec { vec, index } => vec.remove(index),
+            #[cfg(not(feature = "std"))]
+            OccupiedRefInner::HashMap(entry) => entry.remove_entry(),
+            OccupiedRefInner::Map { map, key } => map.remove_entry(key).unwrap(),
+        }
+    }
+}
+
+impl<'a, K, V, Q, const N: usize, S> VacantEntryRef<'a, K, V, Q, N, S>
+where
+    K: Eq + Hash + From<&'a Q>,
+    Q: ?Sized,
+    S: BuildHasher + Clone,
+{
+    /// Gets a reference to the borrowed key of the entry.
+    #[inline]
+    pub fn key(&self) -> &'a Q {
+        match &self.inner {
+            VacantRefInner::Map { key, .. } => key,
+            #[cfg(not(feature = "std"))]
+            VacantRefInner::HashMap(entry) => entry.key(),
+        }
+    }
+
+    /// Sets the value of the entry with an owned key created from the
+    /// borrowed one, and returns a mutable reference to it.
+    ///
+    /// The map switches to a `HashMap` if the vector is full.
+    #[inline]
+    pub fn insert(self, value: V) -> &'a mut V {
+        match self.inner {
+            VacantRefInner::Map { map, key } => map.insert_unique(K::from(key), value),
+            #[cfg(not(feature = "std"))]
+            VacantRefInner::HashMap(entry) => entry.insert(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_or_insert() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        *map.entry(1).or_insert(10) += 1;
+        *map.entry(1).or_insert(10) += 1;
+        assert_eq!(map.get(&1), Some(&12));
+        assert_eq!(map.len(), 1);
+
+        map.insert(2, 20);
+        map.insert(3, 30);
+        map.insert(4, 40);
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+        *map.entry(4).or_insert(0) += 1;
+        *map.entry(5).or_insert(50) += 1;
+        assert_eq!(map.get(&4), Some(&41));
+        assert_eq!(map.get(&5), Some(&51));
+    }
+
+    #[test]
+    fn test_entry_transition() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        map.entry(1).or_insert(10);
+        map.entry(2).or_insert(20);
+        map.entry(3).or_insert(30);
+        assert!(matches!(map.inner, InnerContainer::Vec(..)));
+
+        // The vector is full, so this one switches to the hash map
+        let value = map.entry(4).or_insert(40);
+        *value += 1;
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(&1), Some(&10));
+        assert_eq!(map.get(&4), Some(&41));
+    }
+
+    #[test]
+    fn test_entry_or_insert_with_and_default() {
+        let mut map = HybridMap::<i32, Vec<i32>, 2>::new();
+        map.entry(1).or_default().push(1);
+        map.entry(1).or_default().push(2);
+        map.entry(2).or_insert_with(|| vec![0]).push(3);
+        map.entry(3).or_insert_with(|| vec![4]).push(5);
+        assert_eq!(map.get(&1), Some(&vec![1, 2]));
+        assert_eq!(map.get(&2), Some(&vec![0, 3]));
+        assert_eq!(map.get(&3), Some(&vec![4, 5]));
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+    }
+
+    #[test]
+    fn test_entry_or_insert_with_key() {
+        let mut map = HybridMap::<&str, usize, 2>::new();
+        assert_eq!(*map.entry("abc").or_insert_with_key(|k| k.len()), 3);
+        *map.entry("abc")
+            .or_insert_with_key(|_| panic!("should not be called")) += 1;
+        assert_eq!(map.get(&"abc"), Some(&4));
+
+        map.insert("a", 0);
+        assert_eq!(*map.entry("hello").or_insert_with_key(|k| k.len()), 5);
+        assert!(map.spilled());
+        assert_eq!(*map.entry("a").or_insert_with_key(|k| k.len()), 0);
+    }
+
+    #[test]
+    fn test_entry_and_modify() {
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        map.entry(1).and_modify(|v| *v += 1).or_insert(10);
+        map.entry(1).and_modify(|v| *v += 1).or_insert(10);
+        assert_eq!(map.get(&1), Some(&11));
+
+        map.insert(2, 20);
+        map.insert(3, 30);
+        map.entry(3).and_modify(|v| *v += 1).or_insert(0);
+        map.entry(4).and_modify(|v| *v += 1).or_insert(0);
+        assert_eq!(map.get(&3), Some(&31));
+        assert_eq!(map.get(&4), Some(&0));
+    }
+
+    #[test]
+    fn test_entry_and_modify_with_key() {
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        map.insert(3, 10);
+        map.entry(3)
+            .and_modify_with_key(|k, v| *v *= k)
+            .or_insert(0);
+        map.entry(4)
+            .and_modify_with_key(|k, v| *v *= k)
+            .or_insert(0);
+        assert_eq!(map.get(&3), Some(&30));
+        assert_eq!(map.get(&4), Some(&0));
+
+        map.insert(5, 2);
+        assert!(map.spilled());
+        map.entry(5)
+            .and_modify_with_key(|k, v| *v *= k)
+            .or_insert(0);
+        map.entry(6)
+            .and_modify_with_key(|k, v| *v *= k)
+            .or_insert(1);
+        assert_eq!(map.get(&5), Some(&10));
+        assert_eq!(map.get(&6), Some(&1));
+    }
+
+    #[test]
+    fn test_entry_by_ref_clones_only_when_vacant() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(PartialEq, Eq, Hash, Debug)]
+        struct Key(i32);
+        impl Clone for Key {
+            fn clone(&self) -> Self {
+                CLONES.fetch_add(1, Ordering::Relaxed);
+                Key(self.0)
+            }
+        }
+
+        let mut map = HybridMap::<Key, i32, 2>::new();
+        map.insert(Key(1), 10);
+        *map.entry_by_ref(&Key(1)).or_insert(0) += 1;
+        assert_eq!(CLONES.load(Ordering::Relaxed), 0);
+        *map.entry_by_ref(&Key(2)).or_insert(20) += 1;
+        assert_eq!(CLONES.load(Ordering::Relaxed), 1);
+        assert_eq!(map.get(&Key(1)), Some(&11));
+        assert_eq!(map.get(&Key(2)), Some(&21));
+
+        map.insert(Key(3), 30);
+        assert!(map.spilled());
+        let clones = CLONES.load(Ordering::Relaxed);
+        let Entry::Occupied(mut entry) = map.entry_by_ref(&Key(3)) else {
+            panic!("expected an occupied entry");
+        };
+        assert_eq!(entry.key(), &Key(3));
+        *entry.get_mut() += 1;
+        assert_eq!(entry.get(), &31);
+        assert_eq!(CLONES.load(Ordering::Relaxed), clones);
+        map.entry_by_ref(&Key(3))
+            .and_modify_with_key(|k, v| *v += k.0);
+        map.entry_by_ref(&Key(4)).or_insert(40);
+        assert_eq!(CLONES.load(Ordering::Relaxed), clones + 2);
+        assert_eq!(map.get(&Key(3)), Some(&34));
+        assert_eq!(map.len(), 4);
+
+        let Entry::Occupied(entry) = map.entry_by_ref(&Key(4)) else {
+            panic!("expected an occupied entry");
+        };
+        assert_eq!(entry.remove_entry(), (Key(4), 40));
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn test_entry_by_ref_key_is_the_stored_key() {
+        // Equal keys that differ by a tag ignored by Eq and Hash.
+        #[derive(Clone, Debug)]
+        struct Key(i32, &'static str);
+        impl PartialEq for Key {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+        impl Eq for Key {}
+        impl Hash for Key {
+            fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+
+        for len in [1, 4] {
+            let mut map: HybridMap<Key, i32, 2> = (0..len).map(|i| (Key(i, "stored"), i)).collect();
+            let query = Key(0, "query");
+            let Entry::Occupied(entry) = map.entry_by_ref(&query) else {
+                panic!("expected an occupied entry");
+            };
+            assert_eq!(entry.key().1, "stored");
+            assert_eq!(entry.remove_entry().0 .1, "stored");
+        }
+    }
+
+    #[test]
+    fn test_entry_key() {
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        map.insert(1, 10);
+        assert_eq!(map.entry(1).key(), &1);
+        assert_eq!(map.entry(2).key(), &2);
+        map.insert(2, 20);
+        map.insert(3, 30);
+        assert_eq!(map.entry(1).key(), &1);
+        assert_eq!(map.entry(4).key(), &4);
+    }
+
+    #[test]
+    fn test_occupied_entry() {
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        match map.entry(2) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.get(), &20);
+                assert_eq!(entry.insert(21), 20);
+                assert_eq!(entry.remove_entry(), (2, 21));
+            }
+            Entry::Vacant(_) => panic!("Should be occupied"),
+        }
+        assert_eq!(map.len(), 1);
+
+        map.insert(2, 20);
+        map.insert(3, 30);
+        match map.entry(3) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), 30),
+            Entry::Vacant(_) => panic!("Should be occupied"),
+        }
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_entry_remove_shrinks() {
+        let mut map: HybridMap<String, i32, 4> = (0..6).map(|i| (i.to_string(), i)).collect();
+        assert!(map.spilled());
+        for i in 0..3 {
+            match map.entry(i.to_string()) {
+                Entry::Occupied(entry) => assert_eq!(entry.remove(), i),
+                Entry::Vacant(_) => panic!("Should be occupied"),
+            }
+            assert!(map.spilled());
+        }
+        // The fourth removal gets down to the shrink threshold of N / 2.
+        match map.entry("3".to_string()) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), "3");
+                assert_eq!(entry.insert(33), 3);
+                *entry.get_mut() += 1;
+                assert_eq!(entry.get(), &34);
+                assert_eq!(entry.remove_entry(), ("3".to_string(), 34));
+            }
+            Entry::Vacant(_) => panic!("Should be occupied"),
+        }
+        assert!(map.is_inline());
+
+        map.insert("6".to_string(), 6);
+        map.insert("7".to_string(), 7);
+        map.ensure_spilled();
+        match map.entry_ref("4") {
+            EntryRef::Occupied(entry) => assert_eq!(entry.remove(), 4),
+            EntryRef::Vacant(_) => panic!("Should be occupied"),
+        }
+        assert!(map.spilled());
+        let key = "5".to_string();
+        match map.entry_by_ref(&key) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), 5),
+            Entry::Vacant(_) => panic!("Should be occupied"),
+        }
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_try_insert() {
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        *map.try_insert(1, 10).unwrap() += 1;
+        assert_eq!(map.get(&1), Some(&11));
+
+        let err = map.try_insert(1, 20).unwrap_err();
+        assert_eq!(err.entry.key(), &1);
+        assert_eq!(err.entry.get(), &11);
+        assert_eq!(err.value, 20);
+        assert_eq!(
+            err.to_string(),
+            "failed to insert 20, key 1 already exists with value 11"
+        );
+        assert_eq!(map.get(&1), Some(&11));
+    }
+
+    #[test]
+    fn test_try_insert_transition() {
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        assert_eq!(map.try_insert(1, 10).unwrap(), &10);
+        assert_eq!(map.try_insert(2, 20).unwrap(), &20);
+        assert!(map.is_inline());
+
+        *map.try_insert(3, 30).unwrap() += 1;
+        assert!(map.spilled());
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&1), Some(&10));
+        assert_eq!(map.get(&3), Some(&31));
+        assert_eq!(map.try_insert(3, 0).unwrap_err().value, 0);
+        assert_eq!(map.get(&3), Some(&31));
+    }
+
+    #[test]
+    fn test_vacant_entry() {
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        match map.entry(1) {
+            Entry::Occupied(_) => panic!("Should be vacant"),
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), 1),
+        }
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_entry_ref_allocates_only_when_vacant() {
+        use std::cell::Cell;
+
+        thread_local! {
+            static CREATED: Cell<usize> = const { Cell::new(0) };
+        }
+
+        // A key counting how many times it is created from a borrowed str.
+        #[derive(PartialEq, Eq, Hash, Debug)]
+        struct Key(String);
+        impl From<&str> for Key {
+            fn from(key: &str) -> Self {
+                CREATED.with(|created| created.set(created.get() + 1));
+                Key(key.to_string())
+            }
+        }
+        impl Borrow<str> for Key {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        let mut map = HybridMap::<Key, i32, 2>::new();
+        *map.entry_ref("a").or_insert(0) += 1;
+        assert_eq!(CREATED.with(Cell::get), 1);
+        *map.entry_ref("a").or_insert(0) += 1;
+        assert_eq!(CREATED.with(Cell::get), 1);
+        assert_eq!(map.get("a"), Some(&2));
+
+        *map.entry_ref("b").or_default() += 1;
+        *map.entry_ref("c").or_insert_with(|| 10) += 1;
+        assert!(map.spilled());
+        assert_eq!(CREATED.with(Cell::get), 3);
+        *map.entry_ref("c").and_modify(|v| *v += 1).or_insert(0) += 1;
+        assert_eq!(CREATED.with(Cell::get), 3);
+        assert_eq!(map.get("c"), Some(&13));
+        assert_eq!(map.entry_ref("d").key(), "d");
+        assert_eq!(CREATED.with(Cell::get), 3);
+    }
+
+    #[test]
+    fn test_occupied_entry_ref() {
+        let mut map = HybridMap::<String, i32, 2>::new();
+        map.insert("a".to_string(), 1);
+        for _ in 0..2 {
+            match map.entry_ref("a") {
+                EntryRef::Occupied(mut entry) => {
+                    assert_eq!(entry.key(), "a");
+                    assert_eq!(entry.get(), &1);
+                    assert_eq!(entry.insert(2), 1);
+                    assert_eq!(entry.remove_entry(), ("a".to_string(), 2));
+                }
+                EntryRef::Vacant(_) => panic!("Should be occupied"),
+            }
+            assert!(!map.contains_key("a"));
+            map.insert("a".to_string(), 1);
+            // Run again once spilled.
+            map.insert("b".to_string(), 2);
+            map.insert("c".to_string(), 3);
+        }
+        assert!(map.spilled());
+        match map.entry_ref("z") {
+            EntryRef::Occupied(_) => panic!("Should be vacant"),
+            EntryRef::Vacant(entry) => assert_eq!(*entry.insert(26), 26),
+        }
+        assert_eq!(map.get("z"), Some(&26));
+    }
+}
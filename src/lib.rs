@@ -24,16 +24,70 @@
 //! I started benchmarking tiny maps to check whether I should switch from HashMap to BTreeMap for my use case. I also had a naive Vec implementation that was surprisingly faster for my use case. Thus, I made this crate for fun.
 //!
 //! The energy savings this crate may bring probably do not compensate for the energy I used to boil water for my tea while implementing this crate. But it was fun.
+//!
+//! ## no_std
+//!
+//! HybridMap uses the standard library `HashMap` by default. Disable the default `std` feature and enable the `hashbrown` feature to use `hashbrown::HashMap` instead, with only the `alloc` crate.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "hashbrown")))]
+compile_error!("hybridmap requires either the `std` or the `hashbrown` feature");
+
+extern crate alloc;
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{
+    borrow::Borrow,
+    fmt,
+    hash::{BuildHasher, Hash},
+    iter::FusedIterator,
+    ops::{Index, IndexMut},
+};
+#[cfg(not(feature = "std"))]
+use hashbrown::{hash_map, DefaultHashBuilder, HashMap};
 use smallvec::SmallVec;
-use std::{collections::HashMap, hash::Hash};
+#[cfg(feature = "std")]
+use std::collections::{
+    hash_map::{self, RandomState as DefaultHashBuilder},
+    HashMap,
+};
+
+mod entry;
+#[cfg(feature = "preserve_order")]
+mod ordered;
+mod policy;
+#[cfg(feature = "proptest")]
+mod proptest;
+#[cfg(feature = "rayon")]
+mod rayon;
+#[cfg(feature = "serde")]
+mod serde;
+mod sorted;
 
-#[derive(Clone, Debug)]
-enum InnerContainer<K, V, const N: usize> {
+pub use entry::{
+    Entry, EntryRef, OccupiedEntry, OccupiedEntryRef, OccupiedError, VacantEntry, VacantEntryRef,
+};
+/// The error type for `try_reserve`, from the underlying `HashMap`.
+#[cfg(not(feature = "std"))]
+pub use hashbrown::TryReserveError;
+#[cfg(feature = "preserve_order")]
+pub use ordered::{HybridOrderedMap, OrderedIter};
+pub use policy::{CountPolicy, SpillPolicy};
+#[cfg(feature = "rayon")]
+pub use rayon::{IntoParIter, ParIter, ParIterMut};
+pub use sorted::HybridSortedMap;
+/// The error type for `try_reserve`, from the underlying `HashMap`.
+#[cfg(feature = "std")]
+pub use std::collections::TryReserveError;
+
+#[derive(Clone)]
+enum InnerContainer<K, V, const N: usize, S> {
     // We use SmallVec for convenience, as it provides Vec-like ergonomics
     // while not using the memory heap.
-    Vec(SmallVec<(K, V), N>),
+    // The hasher is kept around for when we switch to the HashMap.
+    Vec(SmallVec<(K, V), N>, S),
     // We switch to the standard library HashMap when we reach the capacity
-    HashMap(HashMap<K, V>),
+    HashMap(HashMap<K, V, S>),
 }
 
 /// A map that uses a `Vec` for small numbers of elements and a `HashMap` for
@@ -44,59 +98,55 @@ enum InnerContainer<K, V, const N: usize> {
 ///
 /// The `N` type parameter specifies the maximum number of elements that can be
 /// stored in the `Vec` before it is converted to a `HashMap`. The default value
-/// is 16.
+/// is 16, and a `SpillPolicy` given to `with_spill_policy` can switch earlier.
+/// This is exactly when the inline `SmallVec` would itself move to the heap,
+/// so the `Vec` never allocates.
+/// The map switches back to a `Vec` once removals bring it down to `N / 2`
+/// elements, or to the threshold given to `with_shrink_threshold`.
+///
+/// The `S` type parameter specifies the hasher used by the `HashMap`,
+/// `RandomState` by default like the standard library, or the default hasher
+/// of `hashbrown` without the `std` feature. The hasher is cloned
+/// when the map switches between the `Vec` and the `HashMap`.
 ///
 /// # Examples
 ///
 /// ```
 /// use hybridmap::HybridMap;
 ///
-/// let mut map = HybridMap::<i32, &str, 8>::new();
+/// // Up to 16 entries are stored inline by default.
+/// let mut map = HybridMap::<i32, &str>::new();
 /// map.insert(1, "one");
 /// map.insert(2, "two");
 ///
 /// assert_eq!(map.get(&1), Some(&"one"));
 /// assert_eq!(map.len(), 2);
+/// assert_eq!(map.inline_capacity(), 16);
 /// ```
 ///
-#[derive(Clone, Debug)]
-pub struct HybridMap<K, V, const N: usize = 8> {
-    inner: InnerContainer<K, V, N>,
+pub struct HybridMap<K, V, const N: usize = 16, S = DefaultHashBuilder> {
+    inner: InnerContainer<K, V, N, S>,
+    // The HashMap switches back to a Vec once it gets down to this length.
+    shrink_threshold: usize,
+    // None behaves like CountPolicy, without the dynamic call.
+    spill_policy: Option<&'static dyn SpillPolicy>,
 }
 
-// Default trait.
-impl<K, V, const N: usize> Default for HybridMap<K, V, N>
-where
-    K: Eq + Hash,
-{
-    fn default() -> Self {
-        Self::new()
-    }
-}
+impl<K, V, const N: usize, S> HybridMap<K, V, N, S> {
+    // Wait for the map to be half empty before switching back,
+    // to not switch back and forth around N.
+    const DEFAULT_SHRINK_THRESHOLD: usize = N / 2;
 
-impl<K, V, const N: usize> HybridMap<K, V, N>
-where
-    K: Eq + Hash,
-{
-    /// Creates an empty `HybridMap`.
-    #[inline]
-    pub fn new() -> Self {
-        Self {
-            inner: InnerContainer::Vec(SmallVec::new()),
-        }
-    }
+    // The methods below do not hash, so they do not require K: Eq + Hash.
 
-    /// Creates an empty `HybridMap` with the specified capacity.
+    /// Creates an empty `HybridMap` which will use the given hash builder
+    /// once it switches to a `HashMap`.
     #[inline]
-    pub fn with_capacity(capacity: usize) -> Self {
-        if capacity <= N {
-            Self {
-                inner: InnerContainer::Vec(SmallVec::with_capacity(capacity)),
-            }
-        } else {
-            Self {
-                inner: InnerContainer::HashMap(HashMap::with_capacity(capacity)),
-            }
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            inner: InnerContainer::Vec(SmallVec::new(), hash_builder),
+            shrink_threshold: Self::DEFAULT_SHRINK_THRESHOLD,
+            spill_policy: None,
         }
     }
 
@@ -104,7 +154,7 @@ where
     #[inline]
     pub fn len(&self) -> usize {
         match &self.inner {
-            InnerContainer::Vec(vec) => vec.len(),
+            InnerContainer::Vec(vec, _) => vec.len(),
             InnerContainer::HashMap(map) => map.len(),
         }
     }
@@ -113,447 +163,4402 @@ where
     #[inline]
     pub fn is_empty(&self) -> bool {
         match &self.inner {
-            InnerContainer::Vec(vec) => vec.is_empty(),
+            InnerContainer::Vec(vec, _) => vec.is_empty(),
             InnerContainer::HashMap(map) => map.is_empty(),
         }
     }
 
-    /// Get a reference to an element from the map.
+    /// Returns `true` if the elements are currently stored inline in a `Vec`.
+    ///
+    /// This reflects the current representation of the map, not its logical size.
     #[inline]
-    pub fn get(&self, key: &K) -> Option<&V> {
-        match &self.inner {
-            InnerContainer::Vec(vec) => vec
-                .iter()
-                .find_map(|(k, v)| if k == key { Some(v) } else { None }),
-            InnerContainer::HashMap(map) => map.get(key),
-        }
+    pub fn is_inline(&self) -> bool {
+        matches!(self.inner, InnerContainer::Vec(..))
     }
 
-    /// Get a mutable reference to an element from the map.
+    /// Returns `true` if the elements are currently stored in a `HashMap`.
+    ///
+    /// This reflects the current representation of the map, not its logical size.
     #[inline]
-    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
-        match &mut self.inner {
-            InnerContainer::Vec(vec) => {
-                vec.iter_mut()
-                    .find_map(|(k, v)| if k == key { Some(v) } else { None })
-            }
-            InnerContainer::HashMap(map) => map.get_mut(key),
-        }
+    pub fn spilled(&self) -> bool {
+        !self.is_inline()
     }
 
-    /// Insert an element into the map.
-    ///
-    /// Returns the previous value if the key was already present.
-    /// Returns `None` if the key was not present.
+    /// Clear the map, removing all elements.
     #[inline]
-    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    pub fn clear(&mut self) {
         match &mut self.inner {
-            InnerContainer::Vec(vec) => {
-                // Check if the vec contains the key already
-                for (k, v) in vec.iter_mut() {
-                    if k == &key {
-                        let previous_value = std::mem::replace(v, value);
-                        return Some(previous_value);
-                    }
-                }
-
-                if vec.len() == N {
-                    let mut map = HashMap::new();
-                    for (k, v) in vec.drain(..) {
-                        map.insert(k, v);
-                    }
-                    map.insert(key, value);
-                    self.inner = InnerContainer::HashMap(map);
-                    None
-                } else {
-                    vec.push((key, value));
-                    None
-                }
-            }
-            InnerContainer::HashMap(map) => map.insert(key, value),
+            InnerContainer::Vec(vec, _) => vec.clear(),
+            InnerContainer::HashMap(map) => map.clear(),
         }
     }
+}
 
-    /// Remove an entry from the map by its key.
-    /// returns the entry if it existed.
-    #[inline]
-    pub fn remove_entry(&mut self, key: &K) -> Option<(K, V)> {
-        match &mut self.inner {
-            InnerContainer::Vec(vec) => vec
-                .iter()
-                .position(|(k, _)| k == key)
-                .map(|index| vec.remove(index)),
-            InnerContainer::HashMap(map) => map.remove_entry(key),
+// Default trait.
+impl<K, V, const N: usize, S> Default for HybridMap<K, V, N, S>
+where
+    S: Default,
+{
+    fn default() -> Self {
+        Self {
+            inner: InnerContainer::Vec(SmallVec::new(), S::default()),
+            shrink_threshold: Self::DEFAULT_SHRINK_THRESHOLD,
+            spill_policy: None,
         }
     }
+}
 
-    /// Remove an entry from the map by its key.
-    /// returns the value if it existed.
-    #[inline]
-    pub fn remove(&mut self, key: &K) -> Option<V> {
-        match &mut self.inner {
-            InnerContainer::Vec(vec) => vec
-                .iter()
-                .position(|(k, _)| k == key)
-                .map(|index| vec.remove(index).1),
-            InnerContainer::HashMap(map) => map.remove(key),
+// Clone trait, reusing the existing allocations in clone_from.
+impl<K: Clone, V: Clone, const N: usize, S: Clone> Clone for HybridMap<K, V, N, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            shrink_threshold: self.shrink_threshold,
+            spill_policy: self.spill_policy,
         }
     }
 
-    /// Clear the map, removing all elements.
-    #[inline]
-    pub fn clear(&mut self) {
-        match &mut self.inner {
-            InnerContainer::Vec(vec) => vec.clear(),
-            InnerContainer::HashMap(map) => map.clear(),
+    fn clone_from(&mut self, source: &Self) {
+        self.shrink_threshold = source.shrink_threshold;
+        self.spill_policy = source.spill_policy;
+        match (&mut self.inner, &source.inner) {
+            (
+                InnerContainer::Vec(vec, hash_builder),
+                InnerContainer::Vec(source_vec, source_hash_builder),
+            ) => {
+                vec.clear();
+                vec.extend(source_vec.iter().cloned());
+                hash_builder.clone_from(source_hash_builder);
+            }
+            (InnerContainer::HashMap(map), InnerContainer::HashMap(source_map)) => {
+                map.clone_from(source_map);
+            }
+            (inner, source_inner) => *inner = source_inner.clone(),
         }
     }
+}
 
-    /// Returns an iterator over the entries of the map.
-    #[inline]
-    pub fn iter(&self) -> HybridMapIter<'_, K, V> {
+// Debug trait, showing whether the map has spilled.
+impl<K: fmt::Debug, V: fmt::Debug, const N: usize, S> fmt::Debug for HybridMap<K, V, N, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.inner {
-            InnerContainer::Vec(vec) => HybridMapIter::Vec(vec.iter()),
-            InnerContainer::HashMap(map) => HybridMapIter::HashMap(map.iter()),
+            InnerContainer::Vec(vec, _) => {
+                f.write_str("HybridMap(inline) ")?;
+                f.debug_map()
+                    .entries(vec.iter().map(|(k, v)| (k, v)))
+                    .finish()
+            }
+            InnerContainer::HashMap(map) => {
+                f.write_str("HybridMap(spilled) ")?;
+                f.debug_map().entries(map.iter()).finish()
+            }
         }
     }
+}
 
-    /// Returns a mutable iterator over the entries of the map.
-    #[inline]
-    pub fn iter_mut(&mut self) -> HybridMapIterMut<'_, K, V> {
-        match &mut self.inner {
-            InnerContainer::Vec(vec) => HybridMapIterMut::Vec(vec.iter_mut()),
-            InnerContainer::HashMap(map) => HybridMapIterMut::HashMap(map.iter_mut()),
-        }
+// Debug output sorted by key, returned by debug_sorted.
+struct DebugSorted<'a, K, V, const N: usize, S>(&'a HybridMap<K, V, N, S>);
+
+impl<K, V, const N: usize, S> fmt::Debug for DebugSorted<'_, K, V, N, S>
+where
+    K: Ord + Hash + fmt::Debug,
+    V: fmt::Debug,
+    S: BuildHasher + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.0.sorted_iter()).finish()
     }
 }
 
-/// An iterator over the entries of a `HybridMap`.
-pub enum HybridMapIter<'a, K, V> {
-    Vec(std::slice::Iter<'a, (K, V)>),
-    HashMap(std::collections::hash_map::Iter<'a, K, V>),
+// PartialEq trait, regardless of the inner containers.
+impl<K, V, const N: usize, S> PartialEq for HybridMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher + Clone,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key).is_some_and(|v| value == v))
+    }
 }
 
-impl<'a, K, V> Iterator for HybridMapIter<'a, K, V> {
-    type Item = (&'a K, &'a V);
+// Eq trait.
+impl<K, V, const N: usize, S> Eq for HybridMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    V: Eq,
+    S: BuildHasher + Clone,
+{
+}
 
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            HybridMapIter::Vec(iter) => iter.next().map(|(k, v)| (k, v)),
-            HybridMapIter::HashMap(iter) => iter.next(),
-        }
+// PartialEq traits with HashMap, both ways.
+impl<K, V, const N: usize, S, S2> PartialEq<HashMap<K, V, S2>> for HybridMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher + Clone,
+    S2: BuildHasher,
+{
+    fn eq(&self, other: &HashMap<K, V, S2>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key).is_some_and(|v| value == v))
     }
 }
 
-/// A mutable iterator over the entries of a `HybridMap`.
-pub enum HybridMapIterMut<'a, K, V> {
-    Vec(std::slice::IterMut<'a, (K, V)>),
-    HashMap(std::collections::hash_map::IterMut<'a, K, V>),
+impl<K, V, const N: usize, S, S2> PartialEq<HybridMap<K, V, N, S>> for HashMap<K, V, S2>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher + Clone,
+    S2: BuildHasher,
+{
+    fn eq(&self, other: &HybridMap<K, V, N, S>) -> bool {
+        other == self
+    }
 }
 
-impl<'a, K, V> Iterator for HybridMapIterMut<'a, K, V> {
-    type Item = (&'a K, &'a mut V);
+// PartialEq traits with BTreeMap, both ways.
+impl<K, V, const N: usize, S> PartialEq<BTreeMap<K, V>> for HybridMap<K, V, N, S>
+where
+    K: Ord + Hash,
+    V: PartialEq,
+    S: BuildHasher + Clone,
+{
+    fn eq(&self, other: &BTreeMap<K, V>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key).is_some_and(|v| value == v))
+    }
+}
 
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            HybridMapIterMut::Vec(iter) => iter.next().map(|(ref mut k, ref mut v)| (&*k, &mut *v)),
-            HybridMapIterMut::HashMap(iter) => iter.next(),
-        }
+impl<K, V, const N: usize, S> PartialEq<HybridMap<K, V, N, S>> for BTreeMap<K, V>
+where
+    K: Ord + Hash,
+    V: PartialEq,
+    S: BuildHasher + Clone,
+{
+    fn eq(&self, other: &HybridMap<K, V, N, S>) -> bool {
+        other == self
     }
 }
 
-impl<K: Eq + Hash, V, const N: usize> IntoIterator for HybridMap<K, V, N> {
-    type Item = (K, V);
-    type IntoIter = HybridMapIntoIter<K, V, N>;
+// Index trait.
+impl<K, Q, V, const N: usize, S> Index<&Q> for HybridMap<K, V, N, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+    S: BuildHasher + Clone,
+{
+    type Output = V;
 
+    /// Returns a reference to the value corresponding to the supplied key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not present in the map.
     #[inline]
-    fn into_iter(self) -> Self::IntoIter {
-        match self.inner {
-            InnerContainer::Vec(vec) => HybridMapIntoIter::Vec(vec.into_iter()),
-            InnerContainer::HashMap(map) => HybridMapIntoIter::HashMap(map.into_iter()),
-        }
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in HybridMap")
     }
 }
 
-/// A consuming iterator over the entries of a `HybridMap`.
-pub enum HybridMapIntoIter<K, V, const N: usize> {
-    Vec(smallvec::IntoIter<(K, V), N>),
-    HashMap(std::collections::hash_map::IntoIter<K, V>),
+// IndexMut trait.
+impl<K, Q, V, const N: usize, S> IndexMut<&Q> for HybridMap<K, V, N, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+    S: BuildHasher + Clone,
+{
+    /// Returns a mutable reference to the value corresponding to the supplied key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not present in the map.
+    #[inline]
+    fn index_mut(&mut self, key: &Q) -> &mut V {
+        self.get_mut(key).expect("key not found in HybridMap")
+    }
 }
 
-impl<K, V, const N: usize> Iterator for HybridMapIntoIter<K, V, N> {
-    type Item = (K, V);
-
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            HybridMapIntoIter::Vec(iter) => iter.next(),
-            HybridMapIntoIter::HashMap(iter) => iter.next(),
+// FromIterator trait.
+impl<K, V, const N: usize, S> FromIterator<(K, V)> for HybridMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        // Duplicate keys may inflate the size hint, so the map starts inline
+        // and switches to a HashMap only once more than N keys are unique.
+        let mut iter = iter.into_iter();
+        let mut map = Self::with_hasher(S::default());
+        while let Some((key, value)) = iter.next() {
+            let was_inline = map.is_inline();
+            map.insert(key, value);
+            if was_inline && map.spilled() {
+                map.reserve(iter.size_hint().0);
+            }
         }
+        map
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    // Helper function to create a filled map
-    fn filled_map(size: usize) -> HybridMap<i64, i64> {
-        let mut map = HybridMap::<i64, i64>::new();
-        for i in 0..(size as i64) {
-            map.insert(i, i * 10);
+// From array trait.
+impl<K, V, const N: usize, const M: usize> From<[(K, V); M]> for HybridMap<K, V, N>
+where
+    K: Eq + Hash,
+{
+    /// Creates a `HybridMap` from an array of key-value pairs.
+    ///
+    /// If the array contains duplicate keys, the last value wins.
+    ///
+    /// ```
+    /// use hybridmap::HybridMap;
+    ///
+    /// let map = HybridMap::<i32, &str, 8>::from([(1, "one"), (2, "two")]);
+    /// assert_eq!(map.get(&1), Some(&"one"));
+    /// ```
+    fn from(arr: [(K, V); M]) -> Self {
+        if M <= N {
+            let mut map = Self::new();
+            for (key, value) in arr {
+                map.insert(key, value);
+            }
+            map
+        } else {
+            Self {
+                inner: InnerContainer::HashMap(HashMap::from(arr)),
+                shrink_threshold: Self::DEFAULT_SHRINK_THRESHOLD,
+                spill_policy: None,
+            }
         }
-        map
     }
+}
 
-    #[test]
-    fn new_map_is_empty() {
-        let map: HybridMap<i64, i64> = HybridMap::new();
-        assert!(map.get(&1).is_none());
+// From Vec trait, the last value wins for duplicate keys.
+impl<K, V, const N: usize, S> From<Vec<(K, V)>> for HybridMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone + Default,
+{
+    fn from(vec: Vec<(K, V)>) -> Self {
+        vec.into_iter().collect()
     }
+}
 
-    #[test]
-    fn with_capacity_initializes_correct_inner_container() {
-        const TEST_THRESHOLD: usize = 16;
-        let small_map = HybridMap::<i64, i64, TEST_THRESHOLD>::with_capacity(TEST_THRESHOLD - 1);
-        match small_map.inner {
-            InnerContainer::Vec(_) => {}
-            _ => panic!("Should be a Vec"),
+// From HashMap trait.
+impl<K, V, const N: usize, S> From<HashMap<K, V, S>> for HybridMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Creates a `HybridMap` from a `HashMap`.
+    ///
+    /// The `HashMap` is kept as is if it contains more than `N` entries.
+    fn from(map: HashMap<K, V, S>) -> Self {
+        if map.len() <= N {
+            let hasher = map.hasher().clone();
+            Self {
+                inner: InnerContainer::Vec(map.into_iter().collect(), hasher),
+                shrink_threshold: Self::DEFAULT_SHRINK_THRESHOLD,
+                spill_policy: None,
+            }
+        } else {
+            Self {
+                inner: InnerContainer::HashMap(map),
+                shrink_threshold: Self::DEFAULT_SHRINK_THRESHOLD,
+                spill_policy: None,
+            }
         }
+    }
+}
 
-        let large_map = HybridMap::<i64, i64, TEST_THRESHOLD>::with_capacity(TEST_THRESHOLD + 1);
-        match large_map.inner {
-            InnerContainer::HashMap(_) => {}
-            _ => panic!("Should be a HashMap"),
+// Into HashMap trait.
+impl<K, V, const N: usize, S> From<HybridMap<K, V, N, S>> for HashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Creates a `HashMap` from a `HybridMap`.
+    ///
+    /// The inner `HashMap` is returned as is if the map has switched to it.
+    fn from(map: HybridMap<K, V, N, S>) -> Self {
+        match map.inner {
+            InnerContainer::Vec(vec, hasher) => {
+                let mut map = HashMap::with_capacity_and_hasher(vec.len(), hasher);
+                map.extend(vec);
+                map
+            }
+            InnerContainer::HashMap(map) => map,
         }
     }
+}
 
-    #[test]
-    fn insert_and_get_work_correctly() {
-        let mut map = filled_map(5);
-        assert_eq!(map.get(&3), Some(&30));
-        assert_eq!(map.insert(3, 35), Some(30));
-        assert_eq!(map.get(&3), Some(&35));
+// Only implemented for the default parameters, so that `HybridMap::collect_with`
+// needs no other type annotation than `M`.
+impl<K, V> HybridMap<K, V>
+where
+    K: Eq + Hash,
+{
+    /// Collects the pairs into a `HybridMap` storing up to `M` entries inline,
+    /// like `collect` with `M` given at the call site.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hybridmap::HybridMap;
+    ///
+    /// let map = HybridMap::collect_with::<4, _>((0..5).map(|i| (i, i)));
+    /// assert_eq!(map.inline_capacity(), 4);
+    /// assert!(map.spilled());
+    /// ```
+    #[inline]
+    pub fn collect_with<const M: usize, I>(iter: I) -> HybridMap<K, V, M>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        iter.into_iter().collect()
+    }
+}
+
+impl<K, V, const N: usize> HybridMap<K, V, N, DefaultHashBuilder> {
+    /// Creates an empty `HybridMap`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_hasher(DefaultHashBuilder::default())
+    }
+
+    /// Creates an empty `HybridMap` stored inline, whatever the capacity.
+    ///
+    /// Unlike `with_capacity`, this never allocates a `HashMap` upfront.
+    /// The map switches to a `HashMap` only if it actually grows past `N`,
+    /// which suits loose upper bounds that rarely materialize.
+    #[inline]
+    pub fn with_capacity_inline(capacity: usize) -> Self {
+        Self {
+            inner: InnerContainer::Vec(
+                SmallVec::with_capacity(capacity.min(N)),
+                DefaultHashBuilder::default(),
+            ),
+            shrink_threshold: Self::DEFAULT_SHRINK_THRESHOLD,
+            spill_policy: None,
+        }
+    }
+}
+
+impl<K, V, const N: usize> HybridMap<K, V, N, DefaultHashBuilder>
+where
+    K: Eq + Hash,
+{
+    /// Creates an empty `HybridMap` with the specified capacity.
+    ///
+    /// Up to `N`, the capacity is reserved in the inline buffer and the entries
+    /// are never moved while inserting up to `capacity` elements. Above `N`,
+    /// the map starts directly as a `HashMap`.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, DefaultHashBuilder::default())
+    }
+
+    /// Creates an empty `HybridMap` stored in a `HashMap`, whatever the capacity.
+    ///
+    /// This is mostly useful to test the `HashMap` code paths without inserting
+    /// more than `N` elements first. The shrink threshold is set to 0, so the
+    /// map stays a `HashMap` until removals leave it empty. Use
+    /// `with_shrink_threshold` to switch back to a `Vec` earlier.
+    #[inline]
+    pub fn spilled_with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: InnerContainer::HashMap(HashMap::with_capacity(capacity)),
+            shrink_threshold: 0,
+            spill_policy: None,
+        }
+    }
+}
+
+impl<K, V, const N: usize, S> HybridMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Creates an empty `HybridMap` with the specified capacity, using
+    /// the given hash builder.
+    ///
+    /// See `with_capacity` for how the capacity is reserved.
+    #[inline]
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        if capacity <= N {
+            Self {
+                inner: InnerContainer::Vec(SmallVec::with_capacity(capacity), hash_builder),
+                shrink_threshold: Self::DEFAULT_SHRINK_THRESHOLD,
+                spill_policy: None,
+            }
+        } else {
+            Self {
+                inner: InnerContainer::HashMap(HashMap::with_capacity_and_hasher(
+                    capacity,
+                    hash_builder,
+                )),
+                shrink_threshold: Self::DEFAULT_SHRINK_THRESHOLD,
+                spill_policy: None,
+            }
+        }
+    }
+
+    /// Creates a `HybridMap` from key-sorted pairs without duplicate keys.
+    ///
+    /// Up to `N` pairs are pushed straight into the `Vec`, skipping the
+    /// duplicate key check of `insert`. Passing unsorted pairs or duplicate
+    /// keys is a logic error on this path, only checked in debug builds.
+    /// Larger inputs are inserted into a `HashMap` directly.
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self
+    where
+        K: Ord,
+        S: Default,
+    {
+        let mut iter = iter.into_iter();
+        let mut vec = SmallVec::<(K, V), N>::new();
+        while vec.len() < N {
+            match iter.next() {
+                Some(entry) => vec.push(entry),
+                None => break,
+            }
+        }
+        match iter.next() {
+            None => {
+                debug_assert!(
+                    vec.windows(2).all(|w| w[0].0 < w[1].0),
+                    "from_sorted_iter requires sorted and unique keys"
+                );
+                Self {
+                    inner: InnerContainer::Vec(vec, S::default()),
+                    shrink_threshold: Self::DEFAULT_SHRINK_THRESHOLD,
+                    spill_policy: None,
+                }
+            }
+            Some(entry) => {
+                let mut map = HashMap::with_capacity_and_hasher(
+                    vec.len() + 1 + iter.size_hint().0,
+                    S::default(),
+                );
+                map.extend(vec);
+                map.insert(entry.0, entry.1);
+                map.extend(iter);
+                Self {
+                    inner: InnerContainer::HashMap(map),
+                    shrink_threshold: Self::DEFAULT_SHRINK_THRESHOLD,
+                    spill_policy: None,
+                }
+            }
+        }
+    }
+
+    /// Sets the length at which the `HashMap` switches back to a `Vec`
+    /// after removals, `N / 2` by default.
+    ///
+    /// The map switches to a `HashMap` above `N` elements, so a threshold
+    /// well below `N` prevents a map hovering around `N` from switching back
+    /// and forth on every insertion and removal. The threshold is capped at `N`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hybridmap::HybridMap;
+    ///
+    /// let map: HybridMap<i32, i32, 8> = (0..9).map(|i| (i, i)).collect();
+    /// let mut map = map.with_shrink_threshold(2);
+    /// assert!(map.spilled());
+    /// map.retain(|k, _| *k < 3);
+    /// assert!(map.spilled());
+    /// map.remove(&2);
+    /// assert!(map.is_inline());
+    /// ```
+    #[inline]
+    pub fn with_shrink_threshold(mut self, threshold: usize) -> Self {
+        self.shrink_threshold = threshold.min(N);
+        self.shrink_after_removal();
+        self
+    }
+
+    /// Returns the length at which the `HashMap` switches back to a `Vec`.
+    #[inline]
+    pub fn shrink_threshold(&self) -> usize {
+        self.shrink_threshold
+    }
+
+    /// Sets the policy deciding when the `Vec` switches to a `HashMap`
+    /// on the following insertions, `CountPolicy` by default.
+    ///
+    /// The map still switches above `N` elements whatever the policy.
+    #[inline]
+    pub fn with_spill_policy(mut self, policy: &'static dyn SpillPolicy) -> Self {
+        self.spill_policy = Some(policy);
+        self
+    }
+
+    /// Returns a reference to the map's hash builder.
+    #[inline]
+    pub fn hasher(&self) -> &S {
+        match &self.inner {
+            InnerContainer::Vec(_, hash_builder) => hash_builder,
+            InnerContainer::HashMap(map) => map.hasher(),
+        }
+    }
+
+    /// Returns the hash of a key under the map's hash builder.
+    ///
+    /// The inline `Vec` keeps the hash builder the `HashMap` will use, so a
+    /// key hashes the same before and after the map spills.
+    #[inline]
+    pub fn hash_of<Q>(&self, key: &Q) -> u64
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        self.hasher().hash_one(key)
+    }
+
+    /// Returns the number of elements the map can hold without reallocating.
+    ///
+    /// This is the capacity of the `Vec` when the map is inline,
+    /// and the capacity of the `HashMap` otherwise.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => vec.capacity(),
+            InnerContainer::HashMap(map) => map.capacity(),
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    ///
+    /// The map switches to a `HashMap` right away if the total exceeds `N`,
+    /// so the entries are moved only once.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) if vec.len() + additional > N => self.spill(additional),
+            InnerContainer::Vec(vec, _) => vec.reserve(additional),
+            InnerContainer::HashMap(map) => map.reserve(additional),
+        }
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements.
+    ///
+    /// Like `reserve`, the map switches to a `HashMap` right away if the
+    /// total exceeds `N`. An error is returned instead of aborting if the
+    /// allocation fails, and the map is then left unchanged.
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) if vec.len().saturating_add(additional) > N => {
+                self.try_spill(additional)
+            }
+            // The entries fit in the inline storage, nothing to allocate.
+            InnerContainer::Vec(..) => Ok(()),
+            InnerContainer::HashMap(map) => map.try_reserve(additional),
+        }
+    }
+
+    /// Shrinks the capacity of the map as much as possible.
+    ///
+    /// The map switches back to a `Vec` if the elements fit in it.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        if self.shrink_to_inline() {
+            if let InnerContainer::Vec(vec, _) = &mut self.inner {
+                vec.shrink_to_fit();
+            }
+        } else if let InnerContainer::HashMap(map) = &mut self.inner {
+            map.shrink_to_fit();
+        }
+    }
+
+    /// Shrinks the capacity of the map with a lower limit.
+    ///
+    /// The capacity stays at least as large as both the length and
+    /// `min_capacity`. If `min_capacity` is at most `N` and the elements fit
+    /// in the inline storage, the map switches back to a `Vec`, whose capacity
+    /// never goes below `N`. Otherwise, a `HashMap` keeps room for
+    /// `min_capacity` elements.
+    #[inline]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        if min_capacity <= N {
+            self.shrink_to_inline();
+        }
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => vec.shrink_to(min_capacity),
+            InnerContainer::HashMap(map) => map.shrink_to(min_capacity),
+        }
+    }
+
+    /// Returns the entries as a slice while the map is stored in a `Vec`,
+    /// in insertion order, or `None` once it has switched to a `HashMap`.
+    #[inline]
+    pub fn as_slice(&self) -> Option<&[(K, V)]> {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => Some(vec),
+            InnerContainer::HashMap(_) => None,
+        }
+    }
+
+    /// Same as `as_slice`, named after `inline_iter`.
+    #[inline]
+    pub fn inline_slice(&self) -> Option<&[(K, V)]> {
+        self.as_slice()
+    }
+
+    /// Returns an iterator over the entries while the map is stored in a `Vec`,
+    /// in insertion order, or `None` once it has switched to a `HashMap`.
+    ///
+    /// Unlike `iter`, this is a plain slice iterator, for a fast path when the
+    /// map is inline.
+    #[inline]
+    pub fn inline_iter(&self) -> Option<core::slice::Iter<'_, (K, V)>> {
+        self.as_slice().map(<[_]>::iter)
+    }
+
+    /// Returns an estimate of the number of bytes allocated on the heap.
+    ///
+    /// This is 0 while the entries fit on the memory stack. Once the map has
+    /// switched to a `HashMap`, the estimate counts the entries and one
+    /// control byte per entry, plus a group of control bytes, ignoring the
+    /// spare buckets of the table and the heap memory owned by the entries.
+    #[inline]
+    pub fn heap_size(&self) -> usize {
+        // Size of the extra control bytes group in the hashbrown tables.
+        const GROUP_WIDTH: usize = 16;
+        match &self.inner {
+            InnerContainer::Vec(vec, _) if vec.spilled() => {
+                vec.capacity() * core::mem::size_of::<(K, V)>()
+            }
+            InnerContainer::Vec(..) => 0,
+            InnerContainer::HashMap(map) if map.capacity() == 0 => 0,
+            InnerContainer::HashMap(map) => {
+                map.capacity() * (core::mem::size_of::<(K, V)>() + 1) + GROUP_WIDTH
+            }
+        }
+    }
+
+    /// Returns the maximum number of elements that can be stored inline, `N`.
+    #[inline]
+    pub fn inline_capacity(&self) -> usize {
+        N
+    }
+
+    /// Get a reference to an element from the map.
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match &self.inner {
+            // Branchless scans of small Copy keys were slower than this early
+            // exit in the get_copy benchmark, up to N = 16.
+            InnerContainer::Vec(vec, _) => {
+                vec.iter()
+                    .find_map(|(k, v)| if k.borrow() == key { Some(v) } else { None })
+            }
+            InnerContainer::HashMap(map) => map.get(key),
+        }
+    }
+
+    /// Get a copy of the value of the given key, or the default value
+    /// if the key is absent. The map is left unchanged.
+    #[inline]
+    pub fn get_copied_or_default<Q>(&self, key: &Q) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Default + Copy,
+    {
+        self.get(key).copied().unwrap_or_default()
+    }
+
+    /// Get a reference to an element from the map, using a binary search
+    /// while the map is stored in a `Vec`.
+    ///
+    /// The result is only correct if the `Vec` is sorted by key, which is the
+    /// case when all the entries were added with `insert_sorted`. See also
+    /// `HybridSortedMap`, which maintains this invariant.
+    #[inline]
+    pub fn get_sorted<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q> + Ord,
+        Q: Ord + Hash + Eq + ?Sized,
+    {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => vec
+                .binary_search_by(|(k, _)| k.borrow().cmp(key))
+                .ok()
+                .map(|index| &vec[index].1),
+            InnerContainer::HashMap(map) => map.get(key),
+        }
+    }
+
+    /// Get the stored key and a reference to the value of the given key.
+    #[inline]
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => vec
+                .iter()
+                .find(|(k, _)| k.borrow() == key)
+                .map(|(k, v)| (k, v)),
+            InnerContainer::HashMap(map) => map.get_key_value(key),
+        }
+    }
+
+    /// Returns `true` if the two maps have no key in common.
+    ///
+    /// The keys of the smaller map are looked up in the larger one.
+    #[inline]
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        let (small, large) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        small.keys().all(|key| !large.contains_key(key))
+    }
+
+    /// Returns the keys present in both maps.
+    ///
+    /// Like `is_disjoint`, the keys of the smaller map are looked up in the
+    /// larger one, and the returned references point into the smaller map.
+    #[inline]
+    pub fn common_keys<'a>(&'a self, other: &'a Self) -> Vec<&'a K> {
+        let (small, large) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        small
+            .keys()
+            .filter(|key| large.contains_key(*key))
+            .collect()
+    }
+
+    /// Groups the entries by the bucket computed by `f`.
+    ///
+    /// The buckets are stored in a `HybridMap` with the same `N` and hash
+    /// builder, and the entries of each bucket are in iteration order.
+    #[inline]
+    pub fn grouped_by<B, F>(&self, mut f: F) -> HybridMap<B, Vec<(&K, &V)>, N, S>
+    where
+        B: Eq + Hash,
+        F: FnMut(&K, &V) -> B,
+    {
+        let mut groups = HybridMap::with_hasher(self.hasher().clone());
+        for (key, value) in self.iter() {
+            groups
+                .entry(f(key, value))
+                .or_insert_with(Vec::new)
+                .push((key, value));
+        }
+        groups
+    }
+
+    /// Returns a new map with the same keys and the values transformed by `f`.
+    ///
+    /// The new map has the same length, so it keeps the representation, hash
+    /// builder, shrink threshold and spill policy of this one.
+    #[inline]
+    pub fn map_values<W, F>(&self, mut f: F) -> HybridMap<K, W, N, S>
+    where
+        K: Clone,
+        F: FnMut(&V) -> W,
+    {
+        let inner = match &self.inner {
+            InnerContainer::Vec(vec, hash_builder) => InnerContainer::Vec(
+                vec.iter().map(|(k, v)| (k.clone(), f(v))).collect(),
+                hash_builder.clone(),
+            ),
+            InnerContainer::HashMap(map) => {
+                let mut values = HashMap::with_capacity_and_hasher(map.len(), map.hasher().clone());
+                values.extend(map.iter().map(|(k, v)| (k.clone(), f(v))));
+                InnerContainer::HashMap(values)
+            }
+        };
+        HybridMap {
+            inner,
+            shrink_threshold: self.shrink_threshold,
+            spill_policy: self.spill_policy,
+        }
+    }
+
+    /// Returns `true` if any entry of the map has the given value.
+    ///
+    /// This scans all the values, in O(n).
+    #[inline]
+    pub fn contains_value(&self, value: &V) -> bool
+    where
+        V: PartialEq,
+    {
+        self.values().any(|v| v == value)
+    }
+
+    /// Returns the key of the first entry found with the given value.
+    ///
+    /// This scans the entries in iteration order, in O(n).
+    #[inline]
+    pub fn find_key(&self, value: &V) -> Option<&K>
+    where
+        V: PartialEq,
+    {
+        self.iter().find(|(_, v)| *v == value).map(|(k, _)| k)
+    }
+
+    /// Returns the entry at the given insertion position while the map is
+    /// stored in a `Vec`.
+    ///
+    /// Always returns `None` once the map has switched to a `HashMap`,
+    /// as its entries have no stable position.
+    #[inline]
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => vec.get(index).map(|(k, v)| (k, v)),
+            InnerContainer::HashMap(_) => None,
+        }
+    }
+
+    /// Returns the first inserted entry while the map is stored in a `Vec`.
+    ///
+    /// Once the map has switched to a `HashMap`, this is the first entry
+    /// returned by `iter`, in an unspecified order.
+    #[inline]
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => vec.first().map(|(k, v)| (k, v)),
+            InnerContainer::HashMap(map) => map.iter().next(),
+        }
+    }
+
+    /// Returns the last inserted entry while the map is stored in a `Vec`.
+    ///
+    /// Once the map has switched to a `HashMap`, this is the last entry
+    /// returned by `iter`, in an unspecified order.
+    #[inline]
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => vec.last().map(|(k, v)| (k, v)),
+            InnerContainer::HashMap(map) => map.iter().last(),
+        }
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => vec.iter().any(|(k, _)| k.borrow() == key),
+            InnerContainer::HashMap(map) => map.contains_key(key),
+        }
+    }
+
+    /// Get a mutable reference to an element from the map.
+    #[inline]
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => {
+                vec.iter_mut()
+                    .find_map(|(k, v)| if (*k).borrow() == key { Some(v) } else { None })
+            }
+            InnerContainer::HashMap(map) => map.get_mut(key),
+        }
+    }
+
+    /// Get references to the values of several keys, in the order of the keys.
+    ///
+    /// Each key is looked up on its own, with a scan of the `Vec` while the map
+    /// is inline, so this mostly saves writing the loop.
+    #[inline]
+    pub fn get_many<'a, I>(&self, keys: I) -> Vec<Option<&V>>
+    where
+        I: IntoIterator<Item = &'a K>,
+        K: 'a,
+    {
+        keys.into_iter().map(|key| self.get(key)).collect()
+    }
+
+    /// Attempts to get mutable references to `M` values in the map at once.
+    ///
+    /// Returns `None` if any of the keys is missing, or if any two keys are equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hybridmap::HybridMap;
+    ///
+    /// let mut map = HybridMap::<i32, i32, 8>::from([(1, 10), (2, 20)]);
+    /// if let Some([a, b]) = map.get_many_mut([&1, &2]) {
+    ///     std::mem::swap(a, b);
+    /// }
+    /// assert_eq!(map.get(&1), Some(&20));
+    /// assert!(map.get_many_mut([&1, &1]).is_none());
+    /// ```
+    #[inline]
+    pub fn get_many_mut<Q, const M: usize>(&mut self, keys: [&Q; M]) -> Option<[&mut V; M]>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        for (i, key) in keys.iter().enumerate() {
+            if keys[..i].contains(key) {
+                return None;
+            }
+        }
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => {
+                let mut indices = [0; M];
+                for (index, key) in indices.iter_mut().zip(keys) {
+                    *index = vec.iter().position(|(k, _)| k.borrow() == key)?;
+                }
+                let entries = vec.get_disjoint_mut(indices).ok()?;
+                Some(entries.map(|(_, v)| v))
+            }
+            InnerContainer::HashMap(map) => {
+                let values = map.get_disjoint_mut(keys);
+                if values.iter().any(Option::is_none) {
+                    return None;
+                }
+                Some(values.map(Option::unwrap))
+            }
+        }
+    }
+
+    /// Get mutable references to the values of two distinct keys at once.
+    ///
+    /// Returns `None` if the keys are equal or if either key is missing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hybridmap::HybridMap;
+    ///
+    /// let mut map = HybridMap::<i32, i32, 8>::from([(1, 10), (2, 20)]);
+    /// if let Some((a, b)) = map.get_pair_mut(&1, &2) {
+    ///     core::mem::swap(a, b);
+    /// }
+    /// assert_eq!(map.get(&1), Some(&20));
+    /// ```
+    #[inline]
+    pub fn get_pair_mut<Q>(&mut self, a: &Q, b: &Q) -> Option<(&mut V, &mut V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_many_mut([a, b]).map(|[a, b]| (a, b))
+    }
+
+    /// Swaps the values of two keys.
+    ///
+    /// Returns `false` and leaves the map unchanged if the keys are equal
+    /// or if either key is missing.
+    #[inline]
+    pub fn swap_values<Q>(&mut self, a: &Q, b: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.get_pair_mut(a, b) {
+            Some((a, b)) => {
+                core::mem::swap(a, b);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Insert an element into the map.
+    ///
+    /// Returns the previous value if the key was already present.
+    /// Returns `None` if the key was not present.
+    #[inline]
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => {
+                // Check if the vec contains the key already
+                for (k, v) in vec.iter_mut() {
+                    if k == &key {
+                        let previous_value = core::mem::replace(v, value);
+                        return Some(previous_value);
+                    }
+                }
+
+                self.insert_unique(key, value);
+                None
+            }
+            InnerContainer::HashMap(map) => map.insert(key, value),
+        }
+    }
+
+    /// Like `insert`, also returning `true` if this insertion switched the map
+    /// from the `Vec` to a `HashMap`.
+    #[inline]
+    pub fn insert_tracked(&mut self, key: K, value: V) -> (Option<V>, bool) {
+        let was_inline = self.is_inline();
+        let previous = self.insert(key, value);
+        (previous, was_inline && self.spilled())
+    }
+
+    /// Like `insert`, returning the change of length: 1 for a new key,
+    /// 0 when replacing the value of an existing key.
+    #[inline]
+    pub fn insert_delta(&mut self, key: K, value: V) -> i8 {
+        match self.insert(key, value) {
+            Some(_) => 0,
+            None => 1,
+        }
+    }
+
+    /// Insert an element into the map, keeping the `Vec` sorted by key.
+    ///
+    /// Returns the previous value if the key was already present. The `Vec`
+    /// must already be sorted, for example by only inserting with this method,
+    /// for the lookups with `get_sorted` to be correct.
+    #[inline]
+    pub fn insert_sorted(&mut self, key: K, value: V) -> Option<V>
+    where
+        K: Ord,
+    {
+        let full =
+            matches!(&self.inner, InnerContainer::Vec(vec, _) if self.should_spill(vec.len()));
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => match vec.binary_search_by(|(k, _)| k.cmp(&key)) {
+                Ok(index) => Some(core::mem::replace(&mut vec[index].1, value)),
+                Err(index) if !full => {
+                    vec.insert(index, (key, value));
+                    None
+                }
+                Err(_) => {
+                    self.spill(1);
+                    self.insert(key, value)
+                }
+            },
+            InnerContainer::HashMap(map) => map.insert(key, value),
+        }
+    }
+
+    /// Returns a mutable reference to the value of the given key,
+    /// inserting the result of `f` if the key is absent.
+    ///
+    /// `f` is only called on a miss, which makes this convenient for caches.
+    #[inline]
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, f: F) -> &mut V {
+        self.entry(key).or_insert_with(f)
+    }
+
+    /// Inserts the value only if the key is absent.
+    ///
+    /// Returns `None` if the value was inserted, or a mutable reference to
+    /// the existing value otherwise, leaving it unchanged. Unlike `try_insert`,
+    /// the rejected value is dropped.
+    #[inline]
+    pub fn insert_if_absent(&mut self, key: K, value: V) -> Option<&mut V> {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Some(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Returns a mutable reference to the value of the given key,
+    /// inserting the default value if the key is absent.
+    #[inline]
+    pub fn get_mut_or_insert_default(&mut self, key: K) -> &mut V
+    where
+        V: Default,
+    {
+        self.entry(key).or_default()
+    }
+
+    /// Like `get_or_insert_with`, with a fallible `f`.
+    ///
+    /// `f` is only called on a miss, and the map is left unchanged
+    /// if it returns an error.
+    #[inline]
+    pub fn get_or_try_insert_with<F, E>(&mut self, key: K, f: F) -> Result<&mut V, E>
+    where
+        F: FnOnce() -> Result<V, E>,
+    {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => Ok(entry.insert(f()?)),
+        }
+    }
+
+    /// Insert an element without checking whether the key is already present.
+    ///
+    /// This skips the scan of the `Vec` for an existing key, which speeds up
+    /// building a map from a source known to have unique keys.
+    /// The map switches to a `HashMap` once the `Vec` is full, as usual.
+    ///
+    /// # Safety
+    ///
+    /// The key must not be present in the map. Inserting a duplicate key is
+    /// undefined behavior, even if the current implementation only ends up
+    /// with duplicate entries. This is checked in debug builds.
+    #[inline]
+    pub unsafe fn insert_unique_unchecked(&mut self, key: K, value: V) {
+        debug_assert!(
+            !self.contains_key(&key),
+            "insert_unique_unchecked called with a duplicate key"
+        );
+        self.insert_unique(key, value);
+    }
+
+    /// Returns `true` if inserting a new key into a `Vec` holding `len`
+    /// entries should switch the map to a `HashMap`.
+    #[inline]
+    fn should_spill(&self, len: usize) -> bool {
+        len >= N
+            || self
+                .spill_policy
+                .is_some_and(|policy| policy.should_spill(len, N))
+    }
+
+    /// Insert an element whose key is known to be absent from the map,
+    /// and return a mutable reference to its value.
+    #[inline]
+    fn insert_unique(&mut self, key: K, value: V) -> &mut V {
+        if matches!(&self.inner, InnerContainer::Vec(vec, _) if self.should_spill(vec.len())) {
+            // Make room for the new entry too, so the HashMap doesn't rehash.
+            self.spill(1);
+        }
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => {
+                let index = vec.len();
+                vec.push((key, value));
+                &mut vec[index].1
+            }
+            InnerContainer::HashMap(map) => map.entry(key).or_insert(value),
+        }
+    }
+
+    /// Switch back to a `Vec` if the `HashMap` got down to the shrink threshold.
+    #[inline]
+    fn shrink_after_removal(&mut self) {
+        if matches!(&self.inner, InnerContainer::HashMap(map) if map.len() <= self.shrink_threshold)
+        {
+            self.shrink_to_inline();
+        }
+    }
+
+    /// Reserve room for `additional` more entries once the map is a `HashMap`.
+    ///
+    /// Unlike `reserve`, this never switches from the `Vec`, so incoming keys
+    /// that turn out to be duplicates don't make the map spill.
+    #[inline]
+    fn reserve_if_spilled(&mut self, additional: usize) {
+        if let InnerContainer::HashMap(map) = &mut self.inner {
+            map.reserve(additional);
+        }
+    }
+
+    /// Returns `true` if removing an entry would switch the map back to a `Vec`.
+    #[inline]
+    fn removal_shrinks(&self) -> bool {
+        matches!(&self.inner, InnerContainer::HashMap(map) if map.len() <= self.shrink_threshold.saturating_add(1))
+    }
+
+    /// Move the entries of the vector into a `HashMap`,
+    /// with room for at least `additional` more entries.
+    fn spill(&mut self, additional: usize) {
+        if let InnerContainer::Vec(vec, hash_builder) = &mut self.inner {
+            let mut map =
+                HashMap::with_capacity_and_hasher(vec.len() + additional, hash_builder.clone());
+            for (k, v) in vec.drain(..) {
+                map.insert(k, v);
+            }
+            self.inner = InnerContainer::HashMap(map);
+        }
+    }
+
+    /// Like `spill`, but returns an error if the allocation fails.
+    fn try_spill(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        if let InnerContainer::Vec(vec, hash_builder) = &mut self.inner {
+            let mut map = HashMap::with_hasher(hash_builder.clone());
+            map.try_reserve(vec.len().saturating_add(additional))?;
+            for (k, v) in vec.drain(..) {
+                map.insert(k, v);
+            }
+            self.inner = InnerContainer::HashMap(map);
+        }
+        Ok(())
+    }
+
+    /// Remove an entry from the map by its key.
+    /// returns the entry if it existed.
+    ///
+    /// The map switches back to a `Vec` when the `HashMap` gets down to
+    /// the shrink threshold, `N / 2` elements by default.
+    #[inline]
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => vec
+                .iter()
+                .position(|(k, _)| k.borrow() == key)
+                .map(|index| vec.remove(index)),
+            InnerContainer::HashMap(map) => {
+                let entry = map.remove_entry(key);
+                if entry.is_some() {
+                    self.shrink_after_removal();
+                }
+                entry
+            }
+        }
+    }
+
+    /// Remove an entry from the map by its key.
+    /// returns the value if it existed.
+    ///
+    /// The map switches back to a `Vec` when the `HashMap` gets down to
+    /// the shrink threshold, `N / 2` elements by default.
+    #[inline]
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Remove an entry from the map by its key, returning its value,
+    /// without preserving the order of the `Vec`.
+    ///
+    /// Unlike `remove`, the last entry of the `Vec` takes the place of the
+    /// removed one, in O(1) instead of shifting all the following entries.
+    /// The insertion order of the remaining entries is thus not preserved.
+    /// It behaves like `remove` once the map has switched to a `HashMap`.
+    #[inline]
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => vec
+                .iter()
+                .position(|(k, _)| k.borrow() == key)
+                .map(|index| vec.swap_remove(index).1),
+            InnerContainer::HashMap(_) => self.remove(key),
+        }
+    }
+
+    /// Like `remove`, returning the change of length: -1 if the key was
+    /// present, 0 otherwise.
+    #[inline]
+    pub fn remove_delta<Q>(&mut self, key: &Q) -> i8
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.remove(key) {
+            Some(_) => -1,
+            None => 0,
+        }
+    }
+
+    /// Moves the value of the `old` key to the `new` key.
+    ///
+    /// Returns `false` and leaves the map unchanged if `old` is absent
+    /// or `new` is already present. The length does not change, so the map
+    /// stays in the same container. In the `Vec`, the entry keeps its position.
+    #[inline]
+    pub fn rekey<Q>(&mut self, old: &Q, new: K) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.contains_key::<K>(&new) {
+            return false;
+        }
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => match vec.iter_mut().find(|(k, _)| (*k).borrow() == old)
+            {
+                Some((key, _)) => {
+                    *key = new;
+                    true
+                }
+                None => false,
+            },
+            InnerContainer::HashMap(map) => match map.remove(old) {
+                Some(value) => {
+                    map.insert(new, value);
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Remove and return an arbitrary entry from the map,
+    /// or `None` if the map is empty.
+    ///
+    /// This is the last inserted entry while the map is stored in a `Vec`.
+    /// The map switches back to a `Vec` when the `HashMap` gets down to
+    /// the shrink threshold, `N / 2` elements by default.
+    #[inline]
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => vec.pop(),
+            InnerContainer::HashMap(map) => {
+                let entry = map.extract_if(|_, _| true).next();
+                if entry.is_some() {
+                    self.shrink_after_removal();
+                }
+                entry
+            }
+        }
+    }
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// The map switches back to a `Vec` when the `HashMap` gets down to
+    /// the shrink threshold, `N / 2` elements by default.
+    #[inline]
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => vec.retain_mut(|(k, v)| f(k, v)),
+            InnerContainer::HashMap(map) => {
+                map.retain(f);
+                self.shrink_after_removal();
+            }
+        }
+    }
+
+    /// Like `retain`, then switches back to a `Vec` if the remaining
+    /// elements fit in it, ignoring the shrink threshold.
+    #[inline]
+    pub fn retain_and_rebalance<F>(&mut self, f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.retain(f);
+        self.rebalance();
+    }
+
+    /// Retains only the elements whose key matches the predicate.
+    #[inline]
+    pub fn retain_keys<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K) -> bool,
+    {
+        self.retain(|k, _| f(k));
+    }
+
+    /// Retains only the elements whose value matches the predicate.
+    #[inline]
+    pub fn retain_values<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&V) -> bool,
+    {
+        self.retain(|_, v| f(v));
+    }
+
+    /// Removes every entry whose value equals `value`, returning how many
+    /// were removed.
+    ///
+    /// Like `retain`, the map switches back to a `Vec` when the `HashMap`
+    /// gets down to the shrink threshold.
+    #[inline]
+    pub fn remove_by_value(&mut self, value: &V) -> usize
+    where
+        V: PartialEq,
+    {
+        let len = self.len();
+        self.retain(|_, v| v != value);
+        len - self.len()
+    }
+
+    /// Removes the given keys from the map, returning how many were present.
+    ///
+    /// While the map is stored in a `Vec`, the keys are removed in a single
+    /// pass instead of shifting the vector once per key.
+    #[inline]
+    pub fn remove_many<'a, I>(&mut self, keys: I) -> usize
+    where
+        I: IntoIterator<Item = &'a K>,
+        K: 'a,
+    {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => {
+                let keys: Vec<&K> = keys.into_iter().collect();
+                let len = vec.len();
+                vec.retain(|(k, _)| !keys.contains(&k));
+                len - vec.len()
+            }
+            InnerContainer::HashMap(map) => {
+                let removed = keys
+                    .into_iter()
+                    .filter(|key| map.remove(*key).is_some())
+                    .count();
+                self.shrink_after_removal();
+                removed
+            }
+        }
+    }
+
+    /// Creates an iterator removing and yielding the entries matching the
+    /// predicate, in one pass.
+    ///
+    /// The entries for which `f` returns `false` stay in the map, as well as
+    /// the entries not visited if the iterator is dropped early. The map does
+    /// not switch back to a `Vec` on its own, see `shrink_to_inline`.
+    #[inline]
+    pub fn extract_if<F>(&mut self, f: F) -> ExtractIf<'_, K, V, N, F>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let inner = match &mut self.inner {
+            InnerContainer::Vec(vec, _) => ExtractIfInner::Vec {
+                vec,
+                index: 0,
+                pred: f,
+            },
+            InnerContainer::HashMap(map) => ExtractIfInner::HashMap(map.extract_if(f)),
+        };
+        ExtractIf { inner }
+    }
+
+    /// Move the entries from the `Vec` to a `HashMap` right away,
+    /// ahead of a burst of insertions.
+    ///
+    /// The map may still switch back to a `Vec` after removals.
+    #[inline]
+    pub fn ensure_spilled(&mut self) {
+        self.spill(0);
+    }
+
+    /// Move the entries back from the `HashMap` to a `Vec`, if they fit.
+    ///
+    /// Returns `true` if the map is stored in a `Vec` after the call.
+    #[inline]
+    pub fn shrink_to_inline(&mut self) -> bool {
+        match &mut self.inner {
+            InnerContainer::Vec(..) => true,
+            InnerContainer::HashMap(map) if map.len() <= N => {
+                let hash_builder = map.hasher().clone();
+                self.inner = InnerContainer::Vec(map.drain().collect(), hash_builder);
+                true
+            }
+            InnerContainer::HashMap(_) => false,
+        }
+    }
+
+    /// Switches the map to the container that suits its current length.
+    ///
+    /// A `HashMap` holding at most `N` entries moves back to a `Vec`, ignoring
+    /// the shrink threshold, and a `Vec` holding more than `N` entries moves to
+    /// a `HashMap`.
+    #[inline]
+    pub fn rebalance(&mut self) {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) if vec.len() > N => self.spill(0),
+            InnerContainer::Vec(..) => {}
+            InnerContainer::HashMap(_) => {
+                self.shrink_to_inline();
+            }
+        }
+    }
+
+    /// Clear the map and switch it back to an empty `Vec`.
+    ///
+    /// Unlike `clear`, this releases the memory of the `HashMap`.
+    #[inline]
+    pub fn clear_and_shrink(&mut self) {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => vec.clear(),
+            InnerContainer::HashMap(map) => {
+                let hash_builder = map.hasher().clone();
+                self.inner = InnerContainer::Vec(SmallVec::new(), hash_builder);
+            }
+        }
+    }
+
+    /// Clears the map, returning all key-value pairs as an iterator.
+    ///
+    /// The map keeps its inner container and its allocated memory for reuse.
+    /// If the returned iterator is dropped before being fully consumed,
+    /// it drops the remaining key-value pairs.
+    #[inline]
+    pub fn drain(&mut self) -> Drain<'_, K, V, N> {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => Drain::Vec(vec.drain(..)),
+            InnerContainer::HashMap(map) => Drain::HashMap(map.drain()),
+        }
+    }
+
+    /// Moves the entries matching the predicate into a new map, and keeps
+    /// the others.
+    ///
+    /// The new map has the same hash builder, shrink threshold and spill
+    /// policy. Both maps end up in the container that suits their length,
+    /// like after `rebalance`.
+    #[inline]
+    pub fn split_off_by<F>(&mut self, mut f: F) -> Self
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        let mut other = Self {
+            inner: InnerContainer::Vec(SmallVec::new(), self.hasher().clone()),
+            shrink_threshold: self.shrink_threshold,
+            spill_policy: self.spill_policy,
+        };
+        for (key, value) in self.extract_if(|k, v| f(k, v)) {
+            other.insert_unique(key, value);
+        }
+        self.rebalance();
+        other
+    }
+
+    /// Moves all the entries of `other` into `self`, leaving `other` empty.
+    ///
+    /// The values of `other` replace the existing ones for duplicate keys.
+    /// Like `FromIterator`, the map only switches to a `HashMap` if the unique
+    /// keys don't fit inline, and the capacity for the remaining entries is
+    /// reserved once it has.
+    #[inline]
+    pub fn append(&mut self, other: &mut Self) {
+        let mut entries = other.drain();
+        self.reserve_if_spilled(entries.size_hint().0);
+        while let Some((key, value)) = entries.next() {
+            let was_inline = self.is_inline();
+            self.insert(key, value);
+            if was_inline {
+                self.reserve_if_spilled(entries.size_hint().0);
+            }
+        }
+    }
+
+    /// Copies all the pairs of `slice` into the map.
+    ///
+    /// The last value wins for duplicate keys. Like `append`, the map only
+    /// switches to a `HashMap` if the unique keys don't fit inline.
+    #[inline]
+    pub fn extend_from_slice(&mut self, slice: &[(K, V)])
+    where
+        K: Copy,
+        V: Copy,
+    {
+        self.reserve_if_spilled(slice.len());
+        for (index, &(key, value)) in slice.iter().enumerate() {
+            let was_inline = self.is_inline();
+            self.insert(key, value);
+            if was_inline {
+                self.reserve_if_spilled(slice.len() - index - 1);
+            }
+        }
+    }
+
+    /// Moves all the entries of `other` into `self`, calling `f` with the key,
+    /// the existing value and the incoming value for duplicate keys.
+    ///
+    /// This can sum counters or concatenate lists across maps. The map only
+    /// switches to a `HashMap` if the unique keys don't fit inline.
+    #[inline]
+    pub fn merge_with<F>(&mut self, other: Self, mut f: F)
+    where
+        F: FnMut(&K, &mut V, V),
+    {
+        self.reserve_if_spilled(other.len());
+        for (key, value) in other {
+            if let Some(existing) = self.get_mut(&key) {
+                f(&key, existing, value);
+            } else {
+                self.insert_unique(key, value);
+            }
+        }
+    }
+
+    /// Converts the map into a `Vec` of its entries.
+    ///
+    /// The entries are in insertion order while the map is stored inline.
+    #[inline]
+    pub fn into_vec(self) -> Vec<(K, V)> {
+        match self.inner {
+            InnerContainer::Vec(vec, _) => vec.into(),
+            InnerContainer::HashMap(map) => map.into_iter().collect(),
+        }
+    }
+
+    /// Converts the map into a `Vec` of its entries, sorted by key.
+    #[inline]
+    pub fn into_sorted_vec(self) -> Vec<(K, V)>
+    where
+        K: Ord,
+    {
+        let mut vec = self.into_vec();
+        // The keys are unique, an unstable sort gives the same order.
+        vec.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        vec
+    }
+
+    /// Converts the map into an iterator over its entries, sorted by key.
+    ///
+    /// An inline map is sorted in place, without allocating.
+    #[inline]
+    pub fn into_sorted_iter(self) -> impl Iterator<Item = (K, V)>
+    where
+        K: Ord,
+    {
+        let mut vec: SmallVec<(K, V), N> = match self.inner {
+            InnerContainer::Vec(vec, _) => vec,
+            InnerContainer::HashMap(map) => map.into_iter().collect(),
+        };
+        // The keys are unique, an unstable sort gives the same order.
+        vec.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        vec.into_iter()
+    }
+
+    /// Converts the map into a `HashMap`, with the map's hash builder.
+    ///
+    /// This is the same as `HashMap::from`: a spilled map gives its `HashMap`
+    /// as is, without moving the entries.
+    #[inline]
+    pub fn into_hashmap(self) -> HashMap<K, V, S> {
+        self.into()
+    }
+
+    /// Returns the inline `SmallVec` of the entries, or gives the map back
+    /// if it has switched to a `HashMap`.
+    #[inline]
+    pub fn try_into_smallvec(self) -> Result<SmallVec<(K, V), N>, Self> {
+        match self.inner {
+            InnerContainer::Vec(vec, _) => Ok(vec),
+            InnerContainer::HashMap(_) => Err(self),
+        }
+    }
+
+    /// Returns an iterator over the entries of the map.
+    #[inline]
+    pub fn iter(&self) -> HybridMapIter<'_, K, V> {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => HybridMapIter::Vec(vec.iter()),
+            InnerContainer::HashMap(map) => HybridMapIter::HashMap(map.iter()),
+        }
+    }
+
+    /// Returns a mutable iterator over the entries of the map.
+    #[inline]
+    pub fn iter_mut(&mut self) -> HybridMapIterMut<'_, K, V> {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => HybridMapIterMut::Vec(vec.iter_mut()),
+            InnerContainer::HashMap(map) => HybridMapIterMut::HashMap(map.iter_mut()),
+        }
+    }
+
+    /// Returns an iterator over the entries of the map, sorted by key.
+    ///
+    /// The references to the entries are collected and sorted upfront,
+    /// in O(n log n).
+    #[inline]
+    pub fn sorted_iter(&self) -> impl Iterator<Item = (&K, &V)>
+    where
+        K: Ord,
+    {
+        let mut entries: Vec<(&K, &V)> = self.iter().collect();
+        entries.sort_unstable_by_key(|(k, _)| *k);
+        entries.into_iter()
+    }
+
+    /// Returns a value formatting the entries sorted by key with `Debug`.
+    ///
+    /// Unlike the `Debug` output of the map, it does not depend on the
+    /// container or the order of the `HashMap`, which suits golden tests.
+    #[inline]
+    pub fn debug_sorted(&self) -> impl fmt::Debug + '_
+    where
+        K: Ord + fmt::Debug,
+        V: fmt::Debug,
+    {
+        DebugSorted(self)
+    }
+
+    /// Returns a mutable iterator over the entries of the map, sorted by key.
+    ///
+    /// The references to the entries are collected and sorted upfront,
+    /// in O(n log n).
+    #[inline]
+    pub fn iter_mut_sorted(&mut self) -> impl Iterator<Item = (&K, &mut V)>
+    where
+        K: Ord,
+    {
+        let mut entries: Vec<(&K, &mut V)> = self.iter_mut().collect();
+        entries.sort_unstable_by_key(|(k, _)| *k);
+        entries.into_iter()
+    }
+
+    /// Returns a mutable iterator over the values of the map, in key order.
+    ///
+    /// Like `iter_mut_sorted`, the entries are sorted upfront, in O(n log n).
+    #[inline]
+    pub fn values_mut_sorted(&mut self) -> impl Iterator<Item = &mut V>
+    where
+        K: Ord,
+    {
+        self.iter_mut_sorted().map(|(_, v)| v)
+    }
+
+    /// Returns an iterator over the keys of the map.
+    #[inline]
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => Keys::Vec(vec.iter()),
+            InnerContainer::HashMap(map) => Keys::HashMap(map.keys()),
+        }
+    }
+
+    /// Returns an iterator over the values of the map.
+    #[inline]
+    pub fn values(&self) -> Values<'_, K, V> {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => Values::Vec(vec.iter()),
+            InnerContainer::HashMap(map) => Values::HashMap(map.values()),
+        }
+    }
+
+    /// Folds the values of the map into an accumulator, in one pass over
+    /// the current container.
+    #[inline]
+    pub fn fold_values<B, F>(&self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, &V) -> B,
+    {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => vec.iter().fold(init, |acc, (_, v)| f(acc, v)),
+            InnerContainer::HashMap(map) => map.values().fold(init, f),
+        }
+    }
+
+    /// Returns a mutable iterator over the values of the map.
+    #[inline]
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => ValuesMut::Vec(vec.iter_mut()),
+            InnerContainer::HashMap(map) => ValuesMut::HashMap(map.values_mut()),
+        }
+    }
+
+    /// Creates a consuming iterator visiting all the keys of the map.
+    #[inline]
+    pub fn into_keys(self) -> IntoKeys<K, V, N> {
+        match self.inner {
+            InnerContainer::Vec(vec, _) => IntoKeys::Vec(vec.into_iter()),
+            InnerContainer::HashMap(map) => IntoKeys::HashMap(map.into_iter()),
+        }
+    }
+
+    /// Creates a consuming iterator visiting all the values of the map.
+    #[inline]
+    pub fn into_values(self) -> IntoValues<K, V, N> {
+        match self.inner {
+            InnerContainer::Vec(vec, _) => IntoValues::Vec(vec.into_iter()),
+            InnerContainer::HashMap(map) => IntoValues::HashMap(map.into_iter()),
+        }
+    }
+}
+
+/// An iterator over the entries of a `HybridMap`.
+pub enum HybridMapIter<'a, K, V> {
+    Vec(core::slice::Iter<'a, (K, V)>),
+    HashMap(hash_map::Iter<'a, K, V>),
+}
+
+impl<'a, K, V> Iterator for HybridMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            HybridMapIter::Vec(iter) => iter.next().map(|(k, v)| (k, v)),
+            HybridMapIter::HashMap(iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            HybridMapIter::Vec(iter) => iter.size_hint(),
+            HybridMapIter::HashMap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+// Manual Clone implementation, as deriving it would require K: Clone and V: Clone.
+impl<K, V> Clone for HybridMapIter<'_, K, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        match self {
+            HybridMapIter::Vec(iter) => HybridMapIter::Vec(iter.clone()),
+            HybridMapIter::HashMap(iter) => HybridMapIter::HashMap(iter.clone()),
+        }
+    }
+}
+
+impl<K, V> ExactSizeIterator for HybridMapIter<'_, K, V> {
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            HybridMapIter::Vec(iter) => iter.len(),
+            HybridMapIter::HashMap(iter) => iter.len(),
+        }
+    }
+}
+
+impl<K, V> FusedIterator for HybridMapIter<'_, K, V> {}
+
+/// Iterates from the back of the inline storage, in reverse insertion order.
+///
+/// A spilled map has no meaningful order, so `next_back` yields elements
+/// in the same unspecified order as `next`.
+impl<K, V> DoubleEndedIterator for HybridMapIter<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            HybridMapIter::Vec(iter) => iter.next_back().map(|(k, v)| (k, v)),
+            HybridMapIter::HashMap(iter) => iter.next(),
+        }
+    }
+}
+
+/// A mutable iterator over the entries of a `HybridMap`.
+pub enum HybridMapIterMut<'a, K, V> {
+    Vec(core::slice::IterMut<'a, (K, V)>),
+    HashMap(hash_map::IterMut<'a, K, V>),
+}
+
+impl<'a, K, V> Iterator for HybridMapIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            HybridMapIterMut::Vec(iter) => iter.next().map(|(ref mut k, ref mut v)| (&*k, &mut *v)),
+            HybridMapIterMut::HashMap(iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            HybridMapIterMut::Vec(iter) => iter.size_hint(),
+            HybridMapIterMut::HashMap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<K, V> ExactSizeIterator for HybridMapIterMut<'_, K, V> {
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            HybridMapIterMut::Vec(iter) => iter.len(),
+            HybridMapIterMut::HashMap(iter) => iter.len(),
+        }
+    }
+}
+
+impl<K, V> FusedIterator for HybridMapIterMut<'_, K, V> {}
+
+/// Iterates from the back of the inline storage, in reverse insertion order.
+///
+/// A spilled map has no meaningful order, so `next_back` yields elements
+/// in the same unspecified order as `next`.
+impl<K, V> DoubleEndedIterator for HybridMapIterMut<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            HybridMapIterMut::Vec(iter) => iter
+                .next_back()
+                .map(|(ref mut k, ref mut v)| (&*k, &mut *v)),
+            HybridMapIterMut::HashMap(iter) => iter.next(),
+        }
+    }
+}
+
+/// An iterator over the keys of a `HybridMap`.
+pub enum Keys<'a, K, V> {
+    Vec(core::slice::Iter<'a, (K, V)>),
+    HashMap(hash_map::Keys<'a, K, V>),
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Keys::Vec(iter) => iter.next().map(|(k, _)| k),
+            Keys::HashMap(iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Keys::Vec(iter) => iter.size_hint(),
+            Keys::HashMap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+/// An iterator over the values of a `HybridMap`.
+pub enum Values<'a, K, V> {
+    Vec(core::slice::Iter<'a, (K, V)>),
+    HashMap(hash_map::Values<'a, K, V>),
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Values::Vec(iter) => iter.next().map(|(_, v)| v),
+            Values::HashMap(iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Values::Vec(iter) => iter.size_hint(),
+            Values::HashMap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+/// A mutable iterator over the values of a `HybridMap`.
+pub enum ValuesMut<'a, K, V> {
+    Vec(core::slice::IterMut<'a, (K, V)>),
+    HashMap(hash_map::ValuesMut<'a, K, V>),
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ValuesMut::Vec(iter) => iter.next().map(|(_, v)| v),
+            ValuesMut::HashMap(iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            ValuesMut::Vec(iter) => iter.size_hint(),
+            ValuesMut::HashMap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<K, V, const N: usize, S> IntoIterator for HybridMap<K, V, N, S> {
+    type Item = (K, V);
+    type IntoIter = HybridMapIntoIter<K, V, N>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        match self.inner {
+            InnerContainer::Vec(vec, _) => HybridMapIntoIter::Vec(vec.into_iter()),
+            InnerContainer::HashMap(map) => HybridMapIntoIter::HashMap(map.into_iter()),
+        }
+    }
+}
+
+/// A consuming iterator over the entries of a `HybridMap`.
+pub enum HybridMapIntoIter<K, V, const N: usize> {
+    Vec(smallvec::IntoIter<(K, V), N>),
+    HashMap(hash_map::IntoIter<K, V>),
+}
+
+impl<K, V, const N: usize> Iterator for HybridMapIntoIter<K, V, N> {
+    type Item = (K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            HybridMapIntoIter::Vec(iter) => iter.next(),
+            HybridMapIntoIter::HashMap(iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            HybridMapIntoIter::Vec(iter) => iter.size_hint(),
+            HybridMapIntoIter::HashMap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<K, V, const N: usize> ExactSizeIterator for HybridMapIntoIter<K, V, N> {
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            HybridMapIntoIter::Vec(iter) => iter.len(),
+            HybridMapIntoIter::HashMap(iter) => iter.len(),
+        }
+    }
+}
+
+impl<K, V, const N: usize> FusedIterator for HybridMapIntoIter<K, V, N> {}
+
+/// A consuming iterator over the keys of a `HybridMap`.
+pub enum IntoKeys<K, V, const N: usize> {
+    Vec(smallvec::IntoIter<(K, V), N>),
+    HashMap(hash_map::IntoIter<K, V>),
+}
+
+impl<K, V, const N: usize> Iterator for IntoKeys<K, V, N> {
+    type Item = K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            IntoKeys::Vec(iter) => iter.next().map(|(k, _)| k),
+            IntoKeys::HashMap(iter) => iter.next().map(|(k, _)| k),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            IntoKeys::Vec(iter) => iter.size_hint(),
+            IntoKeys::HashMap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+/// A consuming iterator over the values of a `HybridMap`.
+pub enum IntoValues<K, V, const N: usize> {
+    Vec(smallvec::IntoIter<(K, V), N>),
+    HashMap(hash_map::IntoIter<K, V>),
+}
+
+impl<K, V, const N: usize> Iterator for IntoValues<K, V, N> {
+    type Item = V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            IntoValues::Vec(iter) => iter.next().map(|(_, v)| v),
+            IntoValues::HashMap(iter) => iter.next().map(|(_, v)| v),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            IntoValues::Vec(iter) => iter.size_hint(),
+            IntoValues::HashMap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+/// A draining iterator over the entries of a `HybridMap`.
+pub enum Drain<'a, K, V, const N: usize> {
+    Vec(smallvec::Drain<'a, (K, V), N>),
+    HashMap(hash_map::Drain<'a, K, V>),
+}
+
+impl<K, V, const N: usize> Iterator for Drain<'_, K, V, N> {
+    type Item = (K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Drain::Vec(iter) => iter.next(),
+            Drain::HashMap(iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Drain::Vec(iter) => iter.size_hint(),
+            Drain::HashMap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+/// An iterator removing the entries of a `HybridMap` matching a predicate.
+pub struct ExtractIf<'a, K, V, const N: usize, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    inner: ExtractIfInner<'a, K, V, N, F>,
+}
+
+enum ExtractIfInner<'a, K, V, const N: usize, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    // The index of the next entry to visit, the entries before it are kept.
+    Vec {
+        vec: &'a mut SmallVec<(K, V), N>,
+        index: usize,
+        pred: F,
+    },
+    HashMap(hash_map::ExtractIf<'a, K, V, F>),
+}
+
+impl<K, V, const N: usize, F> Iterator for ExtractIf<'_, K, V, N, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            ExtractIfInner::Vec { vec, index, pred } => {
+                while *index < vec.len() {
+                    let (k, v) = &mut vec[*index];
+                    if pred(k, v) {
+                        return Some(vec.remove(*index));
+                    }
+                    *index += 1;
+                }
+                None
+            }
+            ExtractIfInner::HashMap(iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            ExtractIfInner::Vec { vec, index, .. } => (0, Some(vec.len() - index)),
+            ExtractIfInner::HashMap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Helper function to create a filled map
+    fn filled_map(size: usize) -> HybridMap<i64, i64> {
+        let mut map = HybridMap::<i64, i64>::new();
+        for i in 0..(size as i64) {
+            map.insert(i, i * 10);
+        }
+        map
+    }
+
+    #[test]
+    fn new_map_is_empty() {
+        let map: HybridMap<i64, i64> = HybridMap::new();
+        assert!(map.get(&1).is_none());
+    }
+
+    #[test]
+    fn with_capacity_initializes_correct_inner_container() {
+        const TEST_THRESHOLD: usize = 16;
+        let small_map = HybridMap::<i64, i64, TEST_THRESHOLD>::with_capacity(TEST_THRESHOLD - 1);
+        match small_map.inner {
+            InnerContainer::Vec(..) => {}
+            _ => panic!("Should be a Vec"),
+        }
+
+        let large_map = HybridMap::<i64, i64, TEST_THRESHOLD>::with_capacity(TEST_THRESHOLD + 1);
+        match large_map.inner {
+            InnerContainer::HashMap(_) => {}
+            _ => panic!("Should be a HashMap"),
+        }
+    }
+
+    #[test]
+    fn insert_and_get_work_correctly() {
+        let mut map = filled_map(5);
+        assert_eq!(map.get(&3), Some(&30));
+        assert_eq!(map.insert(3, 35), Some(30));
+        assert_eq!(map.get(&3), Some(&35));
+    }
+
+    #[test]
+    fn insert_transitions_from_vec_to_hashmap() {
+        const TEST_THRESHOLD: usize = 16;
+        let mut map = filled_map(TEST_THRESHOLD);
+        // This insert should trigger the transition
+        map.insert(TEST_THRESHOLD as i64, TEST_THRESHOLD as i64 * 10);
+        match map.inner {
+            InnerContainer::HashMap(_) => {}
+            _ => panic!("Should have transitioned to HashMap"),
+        }
+    }
+
+    #[test]
+    fn overwrite_existing_value() {
+        const TEST_THRESHOLD: usize = 8;
+        let mut map = HybridMap::<i32, i32, TEST_THRESHOLD>::new();
+        assert_eq!(map.insert(1, 10), None); // First insert
+        assert_eq!(map.insert(1, 20), Some(10)); // Overwrite
+        assert_eq!(map.get(&1), Some(&20)); // Check new value
+    }
+
+    #[test]
+    fn remove_and_reinsert_key() {
+        let mut map = filled_map(5);
+        assert_eq!(map.insert(3, 35), Some(30)); // Overwrite
+        assert_eq!(map.insert(3, 40), Some(35)); // Overwrite again
+        assert_eq!(map.get(&3), Some(&40)); // Check new value
+    }
+
+    #[test]
+    fn capacity_expansion() {
+        const TEST_THRESHOLD: usize = 8;
+        let mut map = HybridMap::<i32, i32, TEST_THRESHOLD>::with_capacity(5);
+        for i in 0..15 {
+            map.insert(i, i * 10);
+        }
+        assert_eq!(map.get(&10), Some(&100));
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+    }
+
+    #[test]
+    fn query_non_existent_key() {
+        let map = filled_map(5);
+        assert_eq!(map.get(&10), None);
+    }
+
+    #[test]
+    fn stress_test_large_insertions() {
+        const TEST_THRESHOLD: usize = 8;
+        let mut map = HybridMap::<i32, i32, TEST_THRESHOLD>::new();
+        for i in 0..1000 {
+            map.insert(i, i * 10);
+        }
+        for i in 0..1000 {
+            assert_eq!(map.get(&i), Some(&(i * 10)));
+        }
+    }
+
+    #[test]
+    pub fn test_len() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        assert_eq!(map.len(), 0);
+        map.insert(1, 10);
+        map.insert(2, 20);
+        map.insert(3, 30);
+        assert_eq!(map.len(), 3);
+        map.insert(4, 40);
+        assert_eq!(map.len(), 4);
+    }
+
+    #[test]
+    pub fn test_default() {
+        let map = HybridMap::<i32, i32, 3>::default();
+        assert_eq!(map.len(), 0);
+    }
+
+    #[test]
+    pub fn test_clone() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        map.insert(3, 30);
+        let map2 = map.clone();
+        assert_eq!(map2.len(), 3);
+        assert_eq!(map2.get(&1), Some(&10));
+        assert_eq!(map2.get(&2), Some(&20));
+        assert_eq!(map2.get(&3), Some(&30));
+        map.insert(4, 40);
+        assert_eq!(map2.len(), 3);
+        let map3 = map.clone();
+        assert_eq!(map3.len(), 4);
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        assert!(map.is_empty());
+        map.insert(1, 10);
+        assert!(!map.is_empty());
+        map.insert(2, 20);
+        map.insert(3, 30);
+        map.insert(4, 40);
+        assert!(!map.is_empty());
+    }
+
+    #[test]
+    fn test_is_inline() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        assert_eq!(map.inline_capacity(), 3);
+        assert!(map.is_inline());
+        assert!(!map.spilled());
+        for i in 0..3 {
+            map.insert(i, i);
+        }
+        assert!(map.is_inline());
+        map.insert(3, 3);
+        assert!(!map.is_inline());
+        assert!(map.spilled());
+        map.clear();
+        // Clearing the map doesn't change its representation
+        assert!(map.spilled());
+        assert_eq!(map.inline_capacity(), 3);
+    }
+
+    #[test]
+    fn test_index() {
+        let mut map = HybridMap::<String, i32, 2>::new();
+        map.insert("a".to_string(), 1);
+        assert_eq!(map["a"], 1);
+        map["a"] += 1;
+        assert_eq!(map[&"a".to_string()], 2);
+
+        map.insert("b".to_string(), 2);
+        map.insert("c".to_string(), 3);
+        assert!(map.spilled());
+        assert_eq!(map["c"], 3);
+        map["c"] += 1;
+        assert_eq!(map["c"], 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "key not found in HybridMap")]
+    fn test_index_missing_key() {
+        let map = HybridMap::<i32, i32, 2>::from([(1, 10)]);
+        let _ = map[&2];
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        assert_eq!(map.capacity(), 4);
+        map.reserve(2);
+        assert!(map.is_inline());
+        assert_eq!(map.capacity(), 4);
+        for i in 0..4 {
+            map.insert(i, i * 10);
+        }
+        assert_eq!(map.capacity(), 4);
+
+        map.insert(4, 40);
+        assert!(map.spilled());
+        assert!(map.capacity() >= 5);
+        map.reserve(100);
+        assert!(map.capacity() >= 105);
+    }
+
+    #[test]
+    fn test_reserve_switches_to_hashmap() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        map.reserve(3);
+        assert!(map.spilled());
+        assert!(map.capacity() >= 5);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&10));
+        assert_eq!(map.get(&2), Some(&20));
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        for i in 0..100 {
+            map.insert(i, i * 10);
+        }
+        map.retain(|k, _| *k < 60);
+        map.shrink_to_fit();
+        assert!(map.spilled());
+        assert!(map.capacity() >= 60);
+
+        map.retain(|k, _| *k < 4);
+        map.shrink_to_fit();
+        assert!(map.is_inline());
+        assert_eq!(map.capacity(), 4);
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(&3), Some(&30));
+    }
+
+    #[test]
+    fn test_contains_key() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        assert!(!map.contains_key(&1));
+        map.insert(1, 10);
+        map.insert(2, 20);
+        assert!(map.contains_key(&1));
+        assert!(!map.contains_key(&3));
+        map.insert(3, 30);
+        map.insert(4, 40);
+        assert!(map.contains_key(&1));
+        assert!(map.contains_key(&4));
+        assert!(!map.contains_key(&5));
+    }
+
+    #[test]
+    fn test_borrowed_lookups() {
+        let mut map = HybridMap::<String, i32, 2>::new();
+        map.insert("abc".to_string(), 1);
+        map.insert("def".to_string(), 2);
+        assert_eq!(map.get("abc"), Some(&1));
+        assert!(map.contains_key("def"));
+        *map.get_mut("abc").unwrap() += 10;
+        assert_eq!(map.remove("abc"), Some(11));
+
+        map.insert("ghi".to_string(), 3);
+        map.insert("jkl".to_string(), 4);
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+        assert_eq!(map.get("ghi"), Some(&3));
+        assert!(map.contains_key("jkl"));
+        *map.get_mut("jkl").unwrap() += 10;
+        assert_eq!(map.remove_entry("jkl"), Some(("jkl".to_string(), 14)));
+        assert_eq!(map.get("xyz"), None);
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        map.insert(1, 10);
+        assert_eq!(map.get_mut(&1), Some(&mut 10));
+        let entry = map.get_mut(&1).unwrap();
+        *entry = 20;
+        assert_eq!(map.get(&1), Some(&20));
+
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        map.insert(3, 30);
+        map.insert(4, 40);
+        let entry = map.get_mut(&1).unwrap();
+        *entry = 30;
+        assert_eq!(map.get(&1), Some(&30));
+    }
+
+    #[test]
+    fn test_get_many_mut() {
+        let mut map = HybridMap::<i32, i32, 3>::from([(1, 10), (2, 20), (3, 30)]);
+        let [a, b, c] = map.get_many_mut([&3, &1, &2]).unwrap();
+        std::mem::swap(a, b);
+        *c += 1;
+        assert_eq!(map.get(&1), Some(&30));
+        assert_eq!(map.get(&2), Some(&21));
+        assert_eq!(map.get(&3), Some(&10));
+
+        map.insert(4, 40);
+        assert!(map.spilled());
+        let [a, b] = map.get_many_mut([&4, &1]).unwrap();
+        std::mem::swap(a, b);
+        assert_eq!(map.get(&1), Some(&40));
+        assert_eq!(map.get(&4), Some(&30));
+    }
+
+    #[test]
+    fn test_get_many_mut_rejections() {
+        let mut map = HybridMap::<i32, i32, 3>::from([(1, 10), (2, 20)]);
+        assert!(map.get_many_mut([&1, &1]).is_none());
+        assert!(map.get_many_mut([&1, &5]).is_none());
+        assert!(map.get_many_mut::<i32, 0>([]).is_some());
+
+        map.insert(3, 30);
+        map.insert(4, 40);
+        assert!(map.spilled());
+        assert!(map.get_many_mut([&1, &2, &1]).is_none());
+        assert!(map.get_many_mut([&1, &5]).is_none());
+        assert_eq!(map.len(), 4);
+    }
+
+    #[test]
+    fn test_remove_entry() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        map.insert(3, 30);
+        assert_eq!(map.remove_entry(&2), Some((2, 20)));
+        assert!(map.len() == 2);
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map.remove_entry(&2), None);
+        map.insert(4, 40);
+        map.insert(5, 50);
+        assert_eq!(map.remove_entry(&12), None);
+        assert_eq!(map.remove_entry(&3), Some((3, 30)));
+        assert!(map.len() == 3);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        map.insert(3, 30);
+        assert_eq!(map.remove(&2), Some(20));
+        assert!(map.len() == 2);
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map.remove(&2), None);
+        map.insert(4, 40);
+        map.insert(5, 50);
+        assert_eq!(map.remove(&12), None);
+        assert_eq!(map.remove(&3), Some(30));
+        assert!(map.len() == 3);
+    }
+
+    #[test]
+    fn test_remove_switches_back_to_vec() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        for i in 0..6 {
+            map.insert(i, i * 10);
+        }
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+
+        // Not yet, to avoid switching back and forth around N
+        map.remove(&0);
+        map.remove_entry(&1);
+        map.remove(&2);
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+        assert_eq!(map.remove(&42), None);
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+
+        map.remove(&3);
+        assert!(matches!(map.inner, InnerContainer::Vec(..)));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&4), Some(&40));
+        assert_eq!(map.get(&5), Some(&50));
+    }
+
+    #[test]
+    fn test_shrink_to_inline() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        assert!(map.shrink_to_inline());
+        for i in 0..6 {
+            map.insert(i, i * 10);
+        }
+        assert!(!map.shrink_to_inline());
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+
+        map.remove(&0);
+        map.remove(&1);
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+        assert!(map.shrink_to_inline());
+        assert!(matches!(map.inner, InnerContainer::Vec(..)));
+        assert_eq!(map.len(), 4);
+        for i in 2..6 {
+            assert_eq!(map.get(&i), Some(&(i * 10)));
+        }
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        for i in 0..4 {
+            map.insert(i, i * 10);
+        }
+        map.retain(|k, v| {
+            *v += 1;
+            k % 2 == 0
+        });
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&0), Some(&1));
+        assert_eq!(map.get(&2), Some(&21));
+
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        for i in 0..10 {
+            map.insert(i, i * 10);
+        }
+        map.retain(|k, _| k % 2 == 0);
+        assert!(map.spilled());
+        assert_eq!(map.len(), 5);
+        map.retain(|k, _| *k < 4);
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&0), Some(&0));
+        assert_eq!(map.get(&2), Some(&20));
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        let drained: Vec<_> = map.drain().collect();
+        assert_eq!(drained, vec![(1, 10), (2, 20)]);
+        assert!(map.is_empty());
+        assert!(map.is_inline());
+
+        for i in 0..5 {
+            map.insert(i, i * 10);
+        }
+        let mut drained: Vec<_> = map.drain().collect();
+        drained.sort();
+        assert_eq!(drained, vec![(0, 0), (1, 10), (2, 20), (3, 30), (4, 40)]);
+        assert!(map.is_empty());
+        assert!(map.spilled());
+    }
+
+    #[test]
+    fn test_drain_dropped_early() {
+        let mut map = HybridMap::<i32, String, 3>::new();
+        map.insert(1, "one".to_string());
+        map.insert(2, "two".to_string());
+        assert_eq!(map.drain().next(), Some((1, "one".to_string())));
+        assert!(map.is_empty());
+        assert_eq!(map.get(&2), None);
+
+        for i in 0..5 {
+            map.insert(i, i.to_string());
+        }
+        let mut drain = map.drain();
+        assert!(drain.next().is_some());
+        drop(drain);
+        assert!(map.is_empty());
+        assert_eq!(map.get(&2), None);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        map.insert(1, 10);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&1), None);
+        map.insert(2, 20);
+        map.insert(3, 30);
+        map.insert(4, 40);
+        map.insert(5, 50);
+        assert_eq!(map.len(), 4);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&4), None);
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        map.insert(3, 30);
+        let mut iter = map.iter();
+        // Ordered
+        assert_eq!(iter.next(), Some((&1, &10)));
+        assert_eq!(iter.next(), Some((&2, &20)));
+        assert_eq!(iter.next(), Some((&3, &30)));
+        assert_eq!(iter.next(), None);
+        map.insert(4, 40);
+        let iter = map.iter();
+        let vec = iter.collect::<Vec<_>>();
+        assert_eq!(vec.len(), 4);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        // Add +1
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        map.insert(3, 30);
+        for (_, v) in map.iter_mut() {
+            *v += 1;
+        }
+        assert_eq!(map.get(&1), Some(&11));
+        assert_eq!(map.get(&2), Some(&21));
+        assert_eq!(map.get(&3), Some(&31));
+
+        map.insert(4, 40);
+        for (_, v) in map.iter_mut() {
+            *v += 1;
+        }
+
+        assert_eq!(map.get(&1), Some(&12));
+        assert_eq!(map.get(&4), Some(&41));
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        assert_eq!(*map.get_or_insert_with(1, || 10), 10);
+        assert_eq!(*map.get_or_insert_with(2, || 20), 20);
+
+        // Hit path, the closure is not called.
+        *map.get_or_insert_with(1, || panic!("should not be called")) += 1;
+        assert_eq!(map.get(&1), Some(&11));
+
+        // Miss path filling the vector.
+        assert_eq!(*map.get_or_insert_with(3, || 30), 30);
+        assert!(map.is_inline());
+
+        // Miss path at the transition threshold.
+        *map.get_or_insert_with(4, || 40) += 1;
+        assert!(map.spilled());
+        assert_eq!(map.get(&4), Some(&41));
+        assert_eq!(map.len(), 4);
+
+        *map.get_or_insert_with(4, || panic!("should not be called")) += 1;
+        assert_eq!(map.get(&4), Some(&42));
+    }
+
+    #[test]
+    fn test_swap_remove() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        map.insert(3, 30);
+        assert_eq!(map.swap_remove(&1), Some(10));
+        assert_eq!(map.swap_remove(&1), None);
+        assert_eq!(map.len(), 2);
+        // The last entry took the place of the removed one.
+        let keys: Vec<_> = map.keys().copied().collect();
+        assert_eq!(keys, vec![3, 2]);
+
+        for i in 4..8 {
+            map.insert(i, i * 10);
+        }
+        assert!(map.spilled());
+        assert_eq!(map.swap_remove(&5), Some(50));
+        assert_eq!(map.swap_remove(&5), None);
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.get(&7), Some(&70));
+    }
+
+    #[test]
+    fn test_pop() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        assert_eq!(map.pop(), None);
+        map.insert(1, 10);
+        map.insert(2, 20);
+        assert_eq!(map.pop(), Some((2, 20)));
+        assert_eq!(map.pop(), Some((1, 10)));
+        assert_eq!(map.pop(), None);
+
+        let mut map: HybridMap<i32, i32, 3> = (0..100).map(|i| (i, i)).collect();
+        assert!(map.spilled());
+        let mut popped = Vec::new();
+        while let Some((k, v)) = map.pop() {
+            assert_eq!(k, v);
+            popped.push(k);
+        }
+        assert!(map.is_empty());
+        assert!(map.is_inline());
+        popped.sort();
+        assert_eq!(popped, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_append() {
+        let mut a = HybridMap::<i32, i32, 4>::new();
+        a.insert(1, 10);
+        a.insert(2, 20);
+        a.insert(3, 30);
+        let mut b = HybridMap::<i32, i32, 4>::new();
+        b.insert(3, 31);
+        b.insert(4, 40);
+        b.insert(5, 50);
+        a.append(&mut b);
+        assert!(b.is_empty());
+        assert!(a.spilled());
+        assert_eq!(a.len(), 5);
+        assert_eq!(a.get(&3), Some(&31));
+        assert_eq!(a.get(&5), Some(&50));
+
+        // Overlapping keys that fit inline don't make the map spill.
+        let mut d: HybridMap<i32, i32, 16> = (0..10).map(|i| (i, i)).collect();
+        let mut e: HybridMap<i32, i32, 16> = (0..10).map(|i| (i, i * 2)).collect();
+        d.append(&mut e);
+        assert!(d.is_inline());
+        assert_eq!(d.len(), 10);
+        assert_eq!(d.get(&9), Some(&18));
+
+        // Appending into an already spilled map.
+        let mut c: HybridMap<i32, i32, 4> = (10..20).map(|i| (i, i)).collect();
+        c.append(&mut a);
+        assert!(a.is_empty());
+        assert_eq!(c.len(), 15);
+        assert_eq!(c.get(&1), Some(&10));
+        assert_eq!(c.get(&19), Some(&19));
+
+        // Small maps stay inline.
+        let mut d = HybridMap::<i32, i32, 4>::new();
+        d.insert(1, 1);
+        let mut e = HybridMap::<i32, i32, 4>::new();
+        e.insert(2, 2);
+        d.append(&mut e);
+        assert!(d.is_inline());
+        assert_eq!(d.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_with() {
+        let mut a = HybridMap::<&str, i32, 4>::new();
+        a.insert("a", 1);
+        a.insert("b", 2);
+        let mut b = HybridMap::<&str, i32, 4>::new();
+        b.insert("b", 3);
+        b.insert("c", 4);
+        a.merge_with(b, |_, existing, incoming| *existing += incoming);
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.get(&"a"), Some(&1));
+        assert_eq!(a.get(&"b"), Some(&5));
+        assert_eq!(a.get(&"c"), Some(&4));
+
+        // Mostly shared keys that fit inline don't make the map spill.
+        let mut counters: HybridMap<i32, i32, 16> = (0..10).map(|i| (i, 1)).collect();
+        let other: HybridMap<i32, i32, 16> = (1..11).map(|i| (i, 1)).collect();
+        counters.merge_with(other, |_, existing, incoming| *existing += incoming);
+        assert!(counters.is_inline());
+        assert_eq!(counters.len(), 11);
+        assert_eq!(counters.get(&5), Some(&2));
+        assert_eq!(counters.get(&10), Some(&1));
+
+        let mut lists = HybridMap::<i32, Vec<i32>, 2>::new();
+        lists.insert(1, vec![1]);
+        let other: HybridMap<i32, Vec<i32>, 2> = (0..5).map(|i| (i, vec![i * 10])).collect();
+        lists.merge_with(other, |key, existing, mut incoming| {
+            assert_eq!(*key, 1);
+            existing.append(&mut incoming);
+        });
+        assert!(lists.spilled());
+        assert_eq!(lists.len(), 5);
+        assert_eq!(lists.get(&1), Some(&vec![1, 10]));
+        assert_eq!(lists.get(&4), Some(&vec![40]));
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut map = HybridMap::<i32, &str, 2>::new();
+        assert_eq!(format!("{:?}", map), "HybridMap(inline) {}");
+        map.insert(1, "one");
+        assert_eq!(format!("{:?}", map), r#"HybridMap(inline) {1: "one"}"#);
+        map.insert(2, "two");
+        map.insert(3, "three");
+        let debug = format!("{:?}", map);
+        assert!(debug.starts_with("HybridMap(spilled) {"));
+        assert!(debug.contains(r#"3: "three""#));
+    }
+
+    #[test]
+    fn test_default_threshold() {
+        let mut map = HybridMap::<i32, i32>::default();
+        for i in 0..16 {
+            map.insert(i, i);
+        }
+        assert!(map.is_inline());
+        map.insert(16, 16);
+        assert!(map.spilled());
+    }
+
+    #[test]
+    fn test_from_sorted_iter() {
+        let source: std::collections::BTreeMap<i32, i32> = (0..4).map(|i| (i, i * 10)).collect();
+        let map = HybridMap::<i32, i32, 4>::from_sorted_iter(source);
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 4);
+        let keys: Vec<_> = map.keys().copied().collect();
+        assert_eq!(keys, vec![0, 1, 2, 3]);
+        assert_eq!(map.get(&2), Some(&20));
+
+        let map = HybridMap::<i32, i32, 4>::from_sorted_iter((0..10).map(|i| (i, i * 10)));
+        assert!(map.spilled());
+        assert_eq!(map.len(), 10);
+        assert_eq!(map.get(&9), Some(&90));
+
+        let map = HybridMap::<i32, i32, 4>::from_sorted_iter(core::iter::empty());
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_get_key_value() {
+        // A key keeping its original spelling, compared by its lowercase form.
+        #[derive(Debug)]
+        struct Key {
+            normalized: String,
+            original: String,
+        }
+        impl Key {
+            fn new(original: &str) -> Self {
+                Key {
+                    normalized: original.to_lowercase(),
+                    original: original.to_string(),
+                }
+            }
+        }
+        impl PartialEq for Key {
+            fn eq(&self, other: &Self) -> bool {
+                self.normalized == other.normalized
+            }
+        }
+        impl Eq for Key {}
+        impl core::hash::Hash for Key {
+            fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+                self.normalized.hash(state);
+            }
+        }
+        impl Borrow<str> for Key {
+            fn borrow(&self) -> &str {
+                &self.normalized
+            }
+        }
+
+        let mut map = HybridMap::<Key, i32, 2>::new();
+        map.insert(Key::new("HeLLo"), 1);
+        let (key, value) = map.get_key_value("hello").unwrap();
+        assert_eq!(key.original, "HeLLo");
+        assert_eq!(*value, 1);
+        assert!(map.get_key_value("world").is_none());
+
+        map.insert(Key::new("World"), 2);
+        map.insert(Key::new("Rust"), 3);
+        assert!(map.spilled());
+        let (key, value) = map.get_key_value("world").unwrap();
+        assert_eq!(key.original, "World");
+        assert_eq!(*value, 2);
+        assert!(map.get_key_value("other").is_none());
+    }
+
+    #[test]
+    fn test_clone_from() {
+        let inline: HybridMap<i32, i32, 4> = (0..3).map(|i| (i, i)).collect();
+        let spilled: HybridMap<i32, i32, 4> = (0..100).map(|i| (i, i * 2)).collect();
+
+        // From an inline source into a spilled map.
+        let mut map = spilled.clone();
+        map.clone_from(&inline);
+        assert!(map.is_inline());
+        assert_eq!(map, inline);
+
+        // Between inline maps.
+        let mut map: HybridMap<i32, i32, 4> = (10..14).map(|i| (i, i)).collect();
+        map.clone_from(&inline);
+        assert_eq!(map, inline);
+        assert_eq!(map.get(&10), None);
+
+        // Between spilled maps.
+        let mut map: HybridMap<i32, i32, 4> = (50..150).map(|i| (i, i)).collect();
+        map.clone_from(&spilled);
+        assert!(map.spilled());
+        assert_eq!(map, spilled);
+
+        // From a spilled source into an inline map.
+        let mut map = inline.clone();
+        map.clone_from(&spilled);
+        assert!(map.spilled());
+        assert_eq!(map, spilled);
+    }
+
+    #[test]
+    fn test_first_last_key_value() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        assert_eq!(map.first_key_value(), None);
+        assert_eq!(map.last_key_value(), None);
+        map.insert(2, 20);
+        map.insert(1, 10);
+        assert_eq!(map.first_key_value(), Some((&2, &20)));
+        assert_eq!(map.last_key_value(), Some((&1, &10)));
+        map.insert(3, 30);
+        assert_eq!(map.last_key_value(), Some((&3, &30)));
+        map.remove(&2);
+        assert_eq!(map.first_key_value(), Some((&1, &10)));
+
+        map.insert(4, 40);
+        map.insert(5, 50);
+        assert!(map.spilled());
+        assert_eq!(map.first_key_value(), map.iter().next());
+        assert_eq!(map.last_key_value(), map.iter().nth(map.len() - 1));
+        assert_ne!(map.first_key_value(), map.last_key_value());
+    }
+
+    #[test]
+    fn test_with_capacity_inline() {
+        let mut map = HybridMap::<i32, i32, 4>::with_capacity_inline(100);
+        assert!(map.is_inline());
+        assert_eq!(map.capacity(), 4);
+        assert!(HybridMap::<i32, i32, 4>::with_capacity(100).spilled());
+
+        for i in 0..4 {
+            map.insert(i, i);
+        }
+        assert!(map.is_inline());
+        map.insert(4, 4);
+        assert!(map.spilled());
+    }
+
+    #[test]
+    fn test_extend_from_slice() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        map.extend_from_slice(&[(1, 10), (2, 20), (1, 11)]);
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&11));
+
+        map.extend_from_slice(&[(3, 30), (4, 40), (5, 50), (2, 21)]);
+        assert!(map.spilled());
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.get(&2), Some(&21));
+        assert_eq!(map.get(&5), Some(&50));
+
+        map.extend_from_slice(&[]);
+        assert_eq!(map.len(), 5);
+
+        // A slice full of duplicates doesn't make the map spill.
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        let slice: Vec<(i32, i32)> = (0..20).map(|i| (i % 3, i)).collect();
+        map.extend_from_slice(&slice);
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&1), Some(&19));
+    }
+
+    #[test]
+    fn test_retain_keys_values() {
+        for len in [3, 10] {
+            let mut map: HybridMap<i32, i32, 4> = (0..len).map(|i| (i, i * 10)).collect();
+            map.retain_keys(|k| k % 2 == 0);
+            assert_eq!(map.len(), ((len + 1) / 2) as usize);
+            assert!(map.keys().all(|k| k % 2 == 0));
+
+            let mut map: HybridMap<i32, i32, 4> = (0..len).map(|i| (i, i * 10)).collect();
+            map.retain_values(|v| *v >= 20);
+            assert_eq!(map.len(), (len - 2) as usize);
+            assert!(!map.contains_key(&1));
+            assert!(map.contains_key(&2));
+        }
+    }
+
+    #[test]
+    fn test_heap_size() {
+        let mut map = HybridMap::<u64, u64, 4>::new();
+        assert_eq!(map.heap_size(), 0);
+        for i in 0..4 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.heap_size(), 0);
+        map.insert(4, 4);
+        assert!(map.spilled());
+        assert!(map.heap_size() >= 5 * core::mem::size_of::<(u64, u64)>());
+        map.clear();
+        map.shrink_to_fit();
+        assert_eq!(map.heap_size(), 0);
+    }
+
+    #[test]
+    fn test_extract_if() {
+        for len in [4, 20] {
+            let mut map: HybridMap<i32, i32, 4> = (0..len).map(|i| (i, i)).collect();
+            let mut extracted: Vec<_> = map.extract_if(|_, v| *v % 2 == 0).collect();
+            extracted.sort();
+            assert_eq!(
+                extracted,
+                (0..len).step_by(2).map(|i| (i, i)).collect::<Vec<_>>()
+            );
+            assert_eq!(map.len(), (len / 2) as usize);
+            assert!(map.values().all(|v| v % 2 == 1));
+        }
+
+        // The inline order of the survivors is kept.
+        let mut map: HybridMap<i32, i32, 8> = (0..6).map(|i| (i, i)).collect();
+        let extracted: Vec<_> = map
+            .extract_if(|_, v| {
+                *v += 10;
+                *v % 2 == 0
+            })
+            .collect();
+        assert_eq!(extracted, vec![(0, 10), (2, 12), (4, 14)]);
+        let survivors: Vec<_> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(survivors, vec![(1, 11), (3, 13), (5, 15)]);
+
+        // Dropping the iterator early keeps the remaining entries.
+        let mut map: HybridMap<i32, i32, 8> = (0..6).map(|i| (i, i)).collect();
+        assert_eq!(map.extract_if(|_, _| true).next(), Some((0, 0)));
+        assert_eq!(map.len(), 5);
+    }
+
+    #[test]
+    fn test_from_vec_into_vec() {
+        let map = HybridMap::<i32, i32, 4>::from(vec![(1, 10), (2, 20), (1, 11)]);
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&11));
+        assert_eq!(map.into_vec(), vec![(1, 11), (2, 20)]);
+
+        let pairs: Vec<_> = (0..20).map(|i| (i, i * 10)).collect();
+        let map = HybridMap::<i32, i32, 4>::from(pairs.clone());
+        assert!(map.spilled());
+        assert_eq!(map.len(), 20);
+        let mut back = map.into_vec();
+        back.sort();
+        assert_eq!(back, pairs);
+    }
+
+    #[test]
+    fn test_sorted_iter() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        for i in [3, 1, 2] {
+            map.insert(i, i * 10);
+        }
+        let sorted: Vec<_> = map.sorted_iter().collect();
+        assert_eq!(sorted, vec![(&1, &10), (&2, &20), (&3, &30)]);
+
+        for i in (4..20).rev() {
+            map.insert(i, i * 10);
+        }
+        assert!(map.spilled());
+        let keys: Vec<_> = map.sorted_iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys.len(), 19);
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_eq_std_maps() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        let hash_map = HashMap::from([(1, 10), (2, 20)]);
+        let btree_map = BTreeMap::from([(2, 20), (1, 10)]);
+        assert_eq!(map, hash_map);
+        assert_eq!(hash_map, map);
+        assert_eq!(map, btree_map);
+        assert_eq!(btree_map, map);
+        assert_ne!(map, HashMap::from([(1, 10), (2, 21)]));
+        assert_ne!(map, BTreeMap::from([(1, 10)]));
+
+        let map: HybridMap<i32, i32, 4> = (0..10).map(|i| (i, i)).collect();
+        assert!(map.spilled());
+        let hash_map: HashMap<i32, i32> = (0..10).map(|i| (i, i)).collect();
+        let btree_map: BTreeMap<i32, i32> = (0..10).map(|i| (i, i)).collect();
+        assert_eq!(map, hash_map);
+        assert_eq!(hash_map, map);
+        assert_eq!(map, btree_map);
+        assert_eq!(btree_map, map);
+        assert_ne!(map, HashMap::from([(1, 1)]));
+    }
+
+    #[test]
+    fn test_try_reserve() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        map.insert(1, 10);
+        assert!(map.try_reserve(3).is_ok());
+        assert!(map.is_inline());
+
+        // Too large to allocate, the error is returned and the map is unchanged.
+        assert!(map.try_reserve(usize::MAX).is_err());
+        assert!(map.is_inline());
+        assert_eq!(map.get(&1), Some(&10));
+
+        assert!(map.try_reserve(100).is_ok());
+        assert!(map.spilled());
+        assert!(map.capacity() >= 101);
+        assert_eq!(map.get(&1), Some(&10));
+        assert!(map.try_reserve(usize::MAX).is_err());
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_from_iter_duplicates_stay_inline() {
+        let map: HybridMap<i32, i32, 4> = (0..1000).map(|i| (i % 3, i)).collect();
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&0), Some(&999));
+        assert_eq!(map.get(&2), Some(&998));
+
+        let map: HybridMap<i32, i32, 4> = (0..1000).map(|i| (i % 5, i)).collect();
+        assert!(map.spilled());
+        assert_eq!(map.len(), 5);
+    }
+
+    #[test]
+    fn test_get_pair_mut() {
+        for len in [3, 10] {
+            let mut map: HybridMap<i32, i32, 4> = (0..len).map(|i| (i, i * 10)).collect();
+            let (a, b) = map.get_pair_mut(&0, &2).unwrap();
+            core::mem::swap(a, b);
+            assert_eq!(map.get(&0), Some(&20));
+            assert_eq!(map.get(&2), Some(&0));
+
+            assert!(map.get_pair_mut(&1, &1).is_none());
+            assert!(map.get_pair_mut(&1, &100).is_none());
+            assert!(map.get_pair_mut(&100, &1).is_none());
+        }
+    }
+
+    #[test]
+    fn test_insert_unique_unchecked() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        for i in 0..10 {
+            // SAFETY: the keys are unique.
+            unsafe { map.insert_unique_unchecked(i, i * 10) };
+            assert_eq!(map.is_inline(), i < 4);
+        }
+        assert_eq!(map.len(), 10);
+        for i in 0..10 {
+            assert_eq!(map.get(&i), Some(&(i * 10)));
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "duplicate key")]
+    fn test_insert_unique_unchecked_duplicate() {
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        map.insert(1, 10);
+        // SAFETY: not safe, but checked in debug builds.
+        unsafe { map.insert_unique_unchecked(1, 11) };
+    }
+
+    #[test]
+    fn test_iter_mut_sorted() {
+        for len in [4, 20] {
+            let mut map: HybridMap<i32, i32, 4> = (0..len).rev().map(|i| (i, 0)).collect();
+            let mut visited = Vec::new();
+            for (order, (k, v)) in map.iter_mut_sorted().enumerate() {
+                visited.push(*k);
+                *v = order as i32;
+            }
+            assert_eq!(visited, (0..len).collect::<Vec<_>>());
+            assert!(map.iter().all(|(k, v)| k == v));
+        }
+    }
+
+    #[test]
+    fn test_shrink_threshold_hysteresis() {
+        let mut map = HybridMap::<i32, i32, 8>::new();
+        assert_eq!(map.shrink_threshold(), 4);
+        for i in 0..9 {
+            map.insert(i, i);
+        }
+        assert!(map.spilled());
+
+        // Hovering around N doesn't switch back and forth.
+        for _ in 0..10 {
+            map.remove(&8);
+            assert!(map.spilled());
+            map.insert(8, 8);
+            assert!(map.spilled());
+        }
+
+        // A map shrinking on every removal flips on every operation.
+        let mut map = HybridMap::<i32, i32, 8>::new().with_shrink_threshold(8);
+        assert_eq!(map.shrink_threshold(), 8);
+        for i in 0..9 {
+            map.insert(i, i);
+        }
+        map.remove(&8);
+        assert!(map.is_inline());
+        map.insert(8, 8);
+        assert!(map.spilled());
+
+        // Low thresholds wait longer, and the threshold is capped at N.
+        let mut map: HybridMap<i32, i32, 8> = (0..9).map(|i| (i, i)).collect();
+        map = map.with_shrink_threshold(1);
+        map.retain(|k, _| *k < 2);
+        assert!(map.spilled());
+        map.remove(&1);
+        assert!(map.is_inline());
+        assert_eq!(map.clone().shrink_threshold(), 1);
+        assert_eq!(map.with_shrink_threshold(100).shrink_threshold(), 8);
+    }
+
+    #[test]
+    fn test_contains_value_find_key() {
+        let mut map = HybridMap::<i32, &str, 3>::new();
+        map.insert(1, "one");
+        map.insert(2, "two");
+        map.insert(3, "one");
+        assert!(map.contains_value(&"two"));
+        assert!(!map.contains_value(&"four"));
+        assert_eq!(map.find_key(&"one"), Some(&1));
+        assert_eq!(map.find_key(&"four"), None);
+
+        map.insert(4, "four");
+        assert!(map.spilled());
+        assert!(map.contains_value(&"four"));
+        assert!(!map.contains_value(&"five"));
+        assert_eq!(map.find_key(&"two"), Some(&2));
+        assert!(matches!(map.find_key(&"one"), Some(&1) | Some(&3)));
+        assert_eq!(map.find_key(&"five"), None);
+    }
+
+    #[test]
+    fn test_as_slice() {
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        assert_eq!(map.as_slice(), Some(&[][..]));
+        map.insert(2, 20);
+        map.insert(1, 10);
+        assert_eq!(map.as_slice(), Some(&[(2, 20), (1, 10)][..]));
+        map.insert(3, 30);
+        assert!(map.spilled());
+        assert_eq!(map.as_slice(), None);
+    }
+
+    #[test]
+    fn test_spilled_with_capacity() {
+        let mut map = HybridMap::<i32, i32, 4>::spilled_with_capacity(8);
+        assert!(map.spilled());
+        assert!(map.is_empty());
+        for i in 0..4 {
+            map.insert(i, i * 10);
+        }
+        assert!(map.spilled());
+        assert_eq!(map.shrink_threshold(), 0);
+        assert_eq!(map.get(&1), Some(&10));
+        for i in (1..4).rev() {
+            assert_eq!(map.remove(&i), Some(i * 10));
+            assert!(map.spilled());
+        }
+        assert_eq!(map.get(&0), Some(&0));
+        map.remove(&0);
+        assert!(map.is_inline());
+
+        assert!(HybridMap::<i32, i32, 4>::spilled_with_capacity(0).spilled());
+    }
+
+    #[test]
+    fn test_remove_many() {
+        let mut map: HybridMap<i32, i32, 8> = (0..6).map(|i| (i, i)).collect();
+        assert_eq!(map.remove_many(&[1, 3, 7, 3]), 2);
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 4);
+        assert!(!map.contains_key(&1));
+        assert!(!map.contains_key(&3));
+        assert_eq!(map.remove_many(&[]), 0);
+
+        let mut map: HybridMap<i32, i32, 8> = (0..20).map(|i| (i, i)).collect();
+        let keys: Vec<i32> = (10..30).collect();
+        assert_eq!(map.remove_many(&keys), 10);
+        assert!(map.spilled());
+        assert_eq!(map.len(), 10);
+        assert_eq!(map.remove_many(&[0, 1, 2, 3, 4, 5, 42]), 6);
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(&9), Some(&9));
+    }
+
+    #[test]
+    fn test_with_capacity_no_reallocation() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        // A reallocation would show as a new address for the entries,
+        // and any copy or early drop of the keys in this counter.
+        static COPIES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(PartialEq, Eq, Hash)]
+        struct Key(u32);
+        impl Clone for Key {
+            fn clone(&self) -> Self {
+                COPIES.fetch_add(1, Ordering::Relaxed);
+                Key(self.0)
+            }
+        }
+        impl Drop for Key {
+            fn drop(&mut self) {
+                COPIES.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let mut map = HybridMap::<Key, u32, 8>::with_capacity(6);
+        assert!(map.is_inline());
+        assert!(map.capacity() >= 6);
+        map.insert(Key(0), 0);
+        let ptr = map.as_slice().unwrap().as_ptr();
+        for i in 1..6 {
+            map.insert(Key(i), i);
+            assert_eq!(map.as_slice().unwrap().as_ptr(), ptr);
+        }
+        assert_eq!(map.heap_size(), 0);
+        assert_eq!(map.len(), 6);
+        assert_eq!(COPIES.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_rebalance() {
+        let mut map: HybridMap<i32, i32, 4> = (0..10).map(|i| (i, i)).collect();
+        map.rebalance();
+        assert!(map.spilled());
+
+        map.retain(|k, _| *k < 4);
+        assert!(map.spilled());
+        map.rebalance();
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(&3), Some(&3));
+
+        // An oversized vector is not reachable through the public API.
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        if let InnerContainer::Vec(vec, _) = &mut map.inner {
+            vec.extend((0..3).map(|i| (i, i)));
+        }
+        map.rebalance();
+        assert!(map.spilled());
+        assert_eq!(map.len(), 3);
+        map.rebalance();
+        assert!(map.spilled());
+    }
+
+    #[test]
+    fn test_get_copied_or_default() {
+        let mut map = HybridMap::<&str, u32, 2>::new();
+        map.insert("a", 1);
+        assert_eq!(map.get_copied_or_default("a"), 1);
+        assert_eq!(map.get_copied_or_default("b"), 0);
+        assert!(!map.contains_key("b"));
+
+        map.insert("b", 2);
+        map.insert("c", 3);
+        assert!(map.spilled());
+        assert_eq!(map.get_copied_or_default("c"), 3);
+        assert_eq!(map.get_copied_or_default("d"), 0);
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn test_get_sorted() {
+        let mut map = HybridMap::<u32, u32, 8>::new();
+        for key in [5, 1, 7, 3, 2] {
+            assert_eq!(map.insert_sorted(key, key * 10), None);
+        }
+        assert_eq!(map.insert_sorted(3, 33), Some(30));
+        let keys: Vec<u32> = map.keys().copied().collect();
+        assert_eq!(keys, [1, 2, 3, 5, 7]);
+        for key in [1, 2, 5, 7] {
+            assert_eq!(map.get_sorted(&key), Some(&(key * 10)));
+        }
+        assert_eq!(map.get_sorted(&3), Some(&33));
+        assert_eq!(map.get_sorted(&4), None);
+        assert_eq!(map.get_sorted(&8), None);
+
+        for key in 10..20 {
+            map.insert_sorted(key, key);
+        }
+        assert!(map.spilled());
+        assert_eq!(map.get_sorted(&15), Some(&15));
+        assert_eq!(map.get_sorted(&1), Some(&10));
+        assert_eq!(map.get_sorted(&4), None);
+    }
+
+    #[test]
+    fn test_get_or_try_insert_with() {
+        let mut map: HybridMap<i32, i32, 2> = [(1, 10)].into_iter().collect();
+        let result: Result<_, ()> = map.get_or_try_insert_with(1, || panic!("called on a hit"));
+        assert_eq!(result, Ok(&mut 10));
+
+        assert_eq!(
+            map.get_or_try_insert_with(2, || Err("failed")),
+            Err("failed")
+        );
+        assert_eq!(map.len(), 1);
+        assert!(!map.contains_key(&2));
+
+        *map.get_or_try_insert_with(2, || Ok::<_, ()>(20)).unwrap() += 1;
+        assert_eq!(map.get(&2), Some(&21));
+        assert!(map.is_inline());
+
+        assert_eq!(map.get_or_try_insert_with(3, || Err(())), Err(()));
+        assert!(map.is_inline());
+        assert_eq!(
+            map.get_or_try_insert_with(3, || Ok::<_, ()>(30)),
+            Ok(&mut 30)
+        );
+        assert!(map.spilled());
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&3), Some(&30));
+    }
+
+    #[test]
+    fn test_shrink_to() {
+        let mut map = HybridMap::<i32, i32, 4>::with_capacity(100);
+        for i in 0..20 {
+            map.insert(i, i);
+        }
+        assert!(map.capacity() >= 100);
+        map.shrink_to(40);
+        assert!(map.capacity() >= 40 && map.capacity() < 100);
+        map.shrink_to(0);
+        assert!(map.capacity() >= 20 && map.capacity() < 40);
+        assert!(map.spilled());
+
+        // The capacity right after retain is not a reliable bound,
+        // as hashbrown does not count the slots of removed entries.
+        map.retain(|k, _| *k < 3);
+        map.shrink_to(10);
+        assert!(map.spilled());
+        assert!(map.capacity() >= 10 && map.capacity() < 40);
+        map.shrink_to(4);
+        assert!(map.is_inline());
+        assert_eq!(map.capacity(), 4);
+        assert_eq!(map.len(), 3);
+        map.shrink_to(0);
+        assert_eq!(map.capacity(), 4);
+        assert_eq!(map.get(&2), Some(&2));
+    }
+
+    #[test]
+    fn test_clear_and_shrink() {
+        let mut map: HybridMap<i32, i32, 4> = (0..10).map(|i| (i, i)).collect();
+        assert!(map.spilled());
+        map.clear_and_shrink();
+        assert!(map.is_inline());
+        assert!(map.is_empty());
+        assert_eq!(map.capacity(), 4);
+        assert_eq!(map.heap_size(), 0);
+
+        map.insert(1, 1);
+        map.clear_and_shrink();
+        assert!(map.is_empty());
+
+        // clear keeps the HashMap.
+        let mut map: HybridMap<i32, i32, 4> = (0..10).map(|i| (i, i)).collect();
+        map.clear();
+        assert!(map.spilled());
+        assert!(map.capacity() >= 10);
+    }
+
+    #[test]
+    fn test_try_into_smallvec() {
+        let map: HybridMap<i32, i32, 4> = (0..3).map(|i| (i, i * 10)).collect();
+        let vec = map.try_into_smallvec().unwrap();
+        assert_eq!(vec.as_slice(), [(0, 0), (1, 10), (2, 20)]);
+
+        let map: HybridMap<i32, i32, 4> = (0..10).map(|i| (i, i * 10)).collect();
+        let map = map.try_into_smallvec().unwrap_err();
+        assert!(map.spilled());
+        assert_eq!(map.len(), 10);
+    }
+
+    #[test]
+    fn test_rekey() {
+        let mut map: HybridMap<&str, i32, 4> = [("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+        assert!(map.rekey("b", "B"));
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), ["a", "B", "c"]);
+        assert_eq!(map.get("B"), Some(&2));
+        assert!(!map.contains_key("b"));
+
+        assert!(!map.rekey("a", "c"));
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.get("c"), Some(&3));
+        assert!(!map.rekey("z", "y"));
+        assert!(!map.contains_key("y"));
+        assert_eq!(map.len(), 3);
+
+        let mut map: HybridMap<i32, i32, 4> = (0..6).map(|i| (i, i * 10)).collect();
+        assert!(map.rekey(&5, 50));
+        assert!(map.spilled());
+        assert_eq!(map.get(&50), Some(&50));
+        assert!(!map.contains_key(&5));
+        assert!(!map.rekey(&0, 1));
+        assert!(!map.rekey(&5, 5));
+        assert_eq!(map.len(), 6);
+        assert_eq!(map.get(&1), Some(&10));
+    }
+
+    #[test]
+    fn test_get_many() {
+        let map: HybridMap<i32, i32, 4> = (0..3).map(|i| (i, i * 10)).collect();
+        assert_eq!(map.get_many(&[2, 5, 0]), [Some(&20), None, Some(&0)]);
+        assert!(map.get_many(&[]).is_empty());
+
+        let map: HybridMap<i32, i32, 4> = (0..10).map(|i| (i, i * 10)).collect();
+        assert!(map.spilled());
+        assert_eq!(
+            map.get_many(&[9, 10, 1, 1]),
+            [Some(&90), None, Some(&10), Some(&10)]
+        );
+    }
+
+    #[test]
+    fn test_is_disjoint() {
+        let empty = HybridMap::<i32, i32, 4>::new();
+        let inline: HybridMap<i32, i32, 4> = (0..3).map(|i| (i, i)).collect();
+        let other_inline: HybridMap<i32, i32, 4> = (3..6).map(|i| (i, i)).collect();
+        let spilled: HybridMap<i32, i32, 4> = (10..20).map(|i| (i, i)).collect();
+        let other_spilled: HybridMap<i32, i32, 4> = (15..30).map(|i| (i, i)).collect();
+        let overlap: HybridMap<i32, i32, 4> = [(2, 0), (19, 0)].into_iter().collect();
+
+        assert!(empty.is_disjoint(&empty));
+        assert!(empty.is_disjoint(&inline));
+        assert!(spilled.is_disjoint(&empty));
+        assert!(inline.is_disjoint(&other_inline));
+        assert!(inline.is_disjoint(&spilled));
+        assert!(spilled.is_disjoint(&inline));
+        assert!(!inline.is_disjoint(&inline));
+        assert!(!spilled.is_disjoint(&other_spilled));
+        assert!(!overlap.is_disjoint(&inline));
+        assert!(!spilled.is_disjoint(&overlap));
+    }
+
+    #[test]
+    fn test_insert_tracked() {
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        assert_eq!(map.insert_tracked(1, 10), (None, false));
+        assert_eq!(map.insert_tracked(2, 20), (None, false));
+        assert_eq!(map.insert_tracked(2, 21), (Some(20), false));
+        assert_eq!(map.insert_tracked(3, 30), (None, true));
+        assert!(map.spilled());
+        assert_eq!(map.insert_tracked(4, 40), (None, false));
+        assert_eq!(map.insert_tracked(3, 31), (Some(30), false));
+    }
+
+    #[test]
+    fn test_inline_slice_and_iter() {
+        let mut map = HybridMap::<i32, i32, 3>::new();
+        for key in [3, 1, 2] {
+            map.insert(key, key * 10);
+        }
+        assert_eq!(map.inline_slice(), Some(&[(3, 30), (1, 10), (2, 20)][..]));
+        let keys: Vec<i32> = map.inline_iter().unwrap().map(|(k, _)| *k).collect();
+        assert_eq!(keys, [3, 1, 2]);
+
+        map.insert(4, 40);
+        assert!(map.inline_slice().is_none());
+        assert!(map.inline_iter().is_none());
+    }
+
+    #[test]
+    fn test_spill_policy() {
+        struct HalfPolicy;
+        impl SpillPolicy for HalfPolicy {
+            fn should_spill(&self, current_len: usize, n: usize) -> bool {
+                current_len >= n / 2
+            }
+        }
+
+        let mut map = HybridMap::<i32, i32, 8>::new().with_spill_policy(&HalfPolicy);
+        for i in 0..4 {
+            map.insert(i, i);
+        }
+        assert!(map.is_inline());
+        map.insert(0, 10);
+        assert!(map.is_inline());
+        map.insert(4, 4);
+        assert!(map.spilled());
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.get(&0), Some(&10));
+
+        // The policy is kept across clones and after switching back.
+        let mut map = map.clone();
+        map.remove(&4);
+        assert!(map.is_inline());
+        *map.entry(5).or_insert(0) += 5;
+        assert!(map.spilled());
+
+        let mut map = HybridMap::<i32, i32, 8>::new().with_spill_policy(&HalfPolicy);
+        for i in 0..4 {
+            map.insert_sorted(i, i);
+        }
+        assert!(map.is_inline());
+        map.insert_sorted(4, 4);
+        assert!(map.spilled());
+
+        let mut map = HybridMap::<i32, i32, 4>::new().with_spill_policy(&CountPolicy);
+        for i in 0..4 {
+            map.insert(i, i);
+        }
+        assert!(map.is_inline());
+        map.insert(4, 4);
+        assert!(map.spilled());
+    }
+
+    #[test]
+    fn test_insert_remove_delta() {
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        let mut total = 0_i64;
+        for (key, value) in [(1, 10), (2, 20), (1, 11), (3, 30), (3, 31)] {
+            total += i64::from(map.insert_delta(key, value));
+        }
+        assert_eq!(total, 3);
+        assert_eq!(map.insert_delta(4, 40), 1);
+        assert_eq!(map.insert_delta(4, 41), 0);
+        assert!(map.spilled());
+
+        assert_eq!(map.remove_delta(&4), -1);
+        assert_eq!(map.remove_delta(&4), 0);
+        assert_eq!(map.remove_delta(&1), -1);
+        assert_eq!(map.remove_delta(&1), 0);
+        assert_eq!(map.remove_delta(&2), -1);
+        assert!(map.is_inline());
+        assert_eq!(map.remove_delta(&2), 0);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_into_sorted_vec() {
+        let map: HybridMap<i32, i32, 4> = [(3, 30), (1, 10), (2, 20)].into_iter().collect();
+        assert_eq!(map.into_sorted_vec(), [(1, 10), (2, 20), (3, 30)]);
+
+        let map: HybridMap<i32, i32, 4> = (0..10).rev().map(|i| (i, i * 10)).collect();
+        assert!(map.spilled());
+        let expected: Vec<(i32, i32)> = (0..10).map(|i| (i, i * 10)).collect();
+        assert_eq!(map.into_sorted_vec(), expected);
+    }
+
+    #[test]
+    fn test_ensure_spilled() {
+        let mut map: HybridMap<i32, i32, 4> = (0..3).map(|i| (i, i * 10)).collect();
+        map.ensure_spilled();
+        assert!(map.spilled());
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&2), Some(&20));
+        map.ensure_spilled();
+        assert!(map.spilled());
+
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        map.ensure_spilled();
+        assert!(map.spilled());
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_collect_with() {
+        let map = HybridMap::collect_with::<4, _>((0..4).map(|i| (i, i)));
+        assert_eq!(map.inline_capacity(), 4);
+        assert!(map.is_inline());
+        let map = HybridMap::collect_with::<4, _>((0..5).map(|i| (i, i)));
+        assert!(map.spilled());
+        assert_eq!(map.len(), 5);
+
+        let map = HybridMap::collect_with::<32, _>((0..20).map(|i| (i, i)));
+        assert!(map.is_inline());
+        assert_eq!(map.get(&19), Some(&19));
+    }
+
+    #[test]
+    fn test_fold_values() {
+        for len in [3, 10] {
+            let map: HybridMap<i32, i32, 4> = (0..len).map(|i| (i, i * 10)).collect();
+            let mut expected = 0;
+            for (_, v) in map.iter() {
+                expected += v;
+            }
+            assert_eq!(map.fold_values(0, |acc, v| acc + v), expected);
+        }
+        let map = HybridMap::<i32, i32, 4>::new();
+        assert_eq!(map.fold_values(7, |acc, v| acc + v), 7);
+    }
+
+    #[test]
+    fn test_retain_and_rebalance() {
+        let mut map: HybridMap<i32, i32, 4> = (0..10).map(|i| (i, i)).collect();
+        map.retain_and_rebalance(|k, _| *k < 6);
+        assert!(map.spilled());
+        map.retain_and_rebalance(|k, v| {
+            *v += 1;
+            *k < 4
+        });
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(&3), Some(&4));
     }
 
     #[test]
-    fn insert_transitions_from_vec_to_hashmap() {
-        const TEST_THRESHOLD: usize = 16;
-        let mut map = filled_map(TEST_THRESHOLD);
-        // This insert should trigger the transition
-        map.insert(TEST_THRESHOLD as i64, TEST_THRESHOLD as i64 * 10);
-        match map.inner {
-            InnerContainer::HashMap(_) => {}
-            _ => panic!("Should have transitioned to HashMap"),
+    fn test_get_index() {
+        let mut map: HybridMap<i32, i32, 3> = [(5, 50), (1, 10), (3, 30)].into_iter().collect();
+        assert_eq!(map.get_index(0), Some((&5, &50)));
+        assert_eq!(map.get_index(2), Some((&3, &30)));
+        assert_eq!(map.get_index(3), None);
+
+        map.insert(4, 40);
+        assert!(map.spilled());
+        assert_eq!(map.get_index(0), None);
+    }
+
+    #[test]
+    fn test_split_off_by() {
+        let mut map: HybridMap<i32, i32, 4> = (0..10).map(|i| (i, i * 10)).collect();
+        let odd = map.split_off_by(|k, _| k % 2 == 1);
+        assert_eq!(map.len(), 5);
+        assert_eq!(odd.len(), 5);
+        assert!(map.spilled());
+        assert!(odd.spilled());
+
+        let mut map: HybridMap<i32, i32, 4> = (0..10).map(|i| (i, i * 10)).collect();
+        let large = map.split_off_by(|k, _| *k >= 3);
+        assert!(map.is_inline());
+        assert!(large.spilled());
+        assert_eq!(map.len(), 3);
+        assert_eq!(large.len(), 7);
+        assert_eq!(large.get(&9), Some(&90));
+        assert_eq!(map.get(&2), Some(&20));
+
+        let mut map: HybridMap<i32, i32, 4> = (0..3).map(|i| (i, i)).collect();
+        let none = map.split_off_by(|_, v| *v > 5);
+        assert!(none.is_empty() && none.is_inline());
+        assert_eq!(map.len(), 3);
+        let all = map.split_off_by(|_, _| true);
+        assert!(map.is_empty());
+        assert_eq!(all.get_index(0), Some((&0, &0)));
+    }
+
+    #[test]
+    fn test_empty_map_without_hash() {
+        // Neither Eq nor Hash.
+        struct Opaque;
+
+        let mut map = HybridMap::<Opaque, i32, 4>::new();
+        assert!(map.is_empty());
+        assert_eq!(map.len(), 0);
+        assert!(map.is_inline());
+        map.clear();
+        let map = HybridMap::<Opaque, i32, 4>::with_capacity_inline(2);
+        assert!(!map.spilled());
+        let map: HybridMap<Opaque, i32, 4> = HybridMap::default();
+        assert!(map.is_empty());
+        let map = HybridMap::<Opaque, i32, 4>::with_hasher(DefaultHashBuilder::default());
+        assert_eq!(map.len(), 0);
+    }
+
+    #[test]
+    fn test_values_mut_sorted() {
+        for len in [4, 10] {
+            let mut map: HybridMap<i32, i32, 4> = (0..len).rev().map(|i| (i, 1)).collect();
+            // Prefix sums in key order.
+            let mut sum = 0;
+            for value in map.values_mut_sorted() {
+                sum += *value;
+                *value = sum;
+            }
+            for key in 0..len {
+                assert_eq!(map.get(&key), Some(&(key + 1)));
+            }
         }
     }
 
     #[test]
-    fn overwrite_existing_value() {
-        const TEST_THRESHOLD: usize = 8;
-        let mut map = HybridMap::<i32, i32, TEST_THRESHOLD>::new();
-        assert_eq!(map.insert(1, 10), None); // First insert
-        assert_eq!(map.insert(1, 20), Some(10)); // Overwrite
-        assert_eq!(map.get(&1), Some(&20)); // Check new value
+    fn test_insert_if_absent() {
+        let mut map = HybridMap::<i32, i32, 2>::new();
+        assert_eq!(map.insert_if_absent(1, 10), None);
+        assert_eq!(map.insert_if_absent(2, 20), None);
+        assert_eq!(map.insert_if_absent(2, 21), Some(&mut 20));
+        assert!(map.is_inline());
+        assert_eq!(map.insert_if_absent(3, 30), None);
+        assert!(map.spilled());
+        *map.insert_if_absent(3, 31).unwrap() += 1;
+        assert_eq!(map.get(&3), Some(&31));
+        assert_eq!(map.get(&2), Some(&20));
+        assert_eq!(map.len(), 3);
     }
 
     #[test]
-    fn remove_and_reinsert_key() {
-        let mut map = filled_map(5);
-        assert_eq!(map.insert(3, 35), Some(30)); // Overwrite
-        assert_eq!(map.insert(3, 40), Some(35)); // Overwrite again
-        assert_eq!(map.get(&3), Some(&40)); // Check new value
+    fn test_common_keys() {
+        let sorted = |mut keys: Vec<&i32>| {
+            keys.sort();
+            keys.into_iter().copied().collect::<Vec<_>>()
+        };
+        let inline: HybridMap<i32, i32, 4> = (0..4).map(|i| (i, i)).collect();
+        let other_inline: HybridMap<i32, i32, 4> = (2..6).map(|i| (i, i)).collect();
+        let spilled: HybridMap<i32, i32, 4> = (3..13).map(|i| (i, i)).collect();
+        let other_spilled: HybridMap<i32, i32, 4> = (10..20).map(|i| (i, i)).collect();
+        let empty = HybridMap::<i32, i32, 4>::new();
+
+        assert_eq!(sorted(inline.common_keys(&other_inline)), [2, 3]);
+        assert_eq!(sorted(inline.common_keys(&spilled)), [3]);
+        assert_eq!(sorted(spilled.common_keys(&inline)), [3]);
+        assert_eq!(sorted(spilled.common_keys(&other_spilled)), [10, 11, 12]);
+        assert_eq!(
+            sorted(spilled.common_keys(&spilled)),
+            (3..13).collect::<Vec<_>>()
+        );
+        assert_eq!(sorted(inline.common_keys(&inline)), [0, 1, 2, 3]);
+        assert!(inline.common_keys(&other_spilled).is_empty());
+        assert!(empty.common_keys(&spilled).is_empty());
     }
 
     #[test]
-    fn capacity_expansion() {
-        const TEST_THRESHOLD: usize = 8;
-        let mut map = HybridMap::<i32, i32, TEST_THRESHOLD>::with_capacity(5);
-        for i in 0..15 {
-            map.insert(i, i * 10);
+    fn test_spills_with_the_smallvec() {
+        let mut vec = SmallVec::<(i32, i32), 4>::new();
+        let mut map = HybridMap::<i32, i32, 4>::new();
+        for i in 0..8 {
+            vec.push((i, i));
+            map.insert(i, i);
+            assert_eq!(map.spilled(), vec.spilled());
+            if map.is_inline() {
+                assert_eq!(map.heap_size(), 0);
+            }
         }
-        assert_eq!(map.get(&10), Some(&100));
-        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+
+        // Neither reserving nor a large capacity make the inline storage allocate.
+        let mut map = HybridMap::<i32, i32, 4>::with_capacity_inline(100);
+        map.reserve(3);
+        assert_eq!(map.heap_size(), 0);
+        map.reserve(5);
+        assert!(map.spilled());
     }
 
     #[test]
-    fn query_non_existent_key() {
-        let map = filled_map(5);
-        assert_eq!(map.get(&10), None);
+    fn test_get_mut_or_insert_default() {
+        let mut map = HybridMap::<&str, u32, 2>::new();
+        *map.get_mut_or_insert_default("a") += 1;
+        *map.get_mut_or_insert_default("a") += 1;
+        *map.get_mut_or_insert_default("b") += 5;
+        assert!(map.is_inline());
+        assert_eq!(map.get("a"), Some(&2));
+        assert_eq!(map.get("b"), Some(&5));
+
+        let value = map.get_mut_or_insert_default("c");
+        assert_eq!(*value, 0);
+        *value = 7;
+        assert!(map.spilled());
+        assert_eq!(map.get("c"), Some(&7));
+        *map.get_mut_or_insert_default("b") += 1;
+        assert_eq!(map.get("b"), Some(&6));
     }
 
     #[test]
-    fn stress_test_large_insertions() {
-        const TEST_THRESHOLD: usize = 8;
-        let mut map = HybridMap::<i32, i32, TEST_THRESHOLD>::new();
-        for i in 0..1000 {
-            map.insert(i, i * 10);
+    fn test_grouped_by() {
+        let map: HybridMap<i32, i32, 4> = (0..7).map(|i| (i, i * 10)).collect();
+        let groups = map.grouped_by(|k, _| k % 2 == 0);
+        assert_eq!(groups.len(), 2);
+        assert!(groups.is_inline());
+        let mut even = groups[&true].clone();
+        even.sort();
+        assert_eq!(even, [(&0, &0), (&2, &20), (&4, &40), (&6, &60)]);
+        let mut odd = groups[&false].clone();
+        odd.sort();
+        assert_eq!(odd, [(&1, &10), (&3, &30), (&5, &50)]);
+
+        // More buckets than N.
+        let groups = map.grouped_by(|k, _| *k);
+        assert!(groups.spilled());
+        assert_eq!(groups[&3], [(&3, &30)]);
+    }
+
+    #[test]
+    fn test_debug_sorted() {
+        let entries = [(3, "c"), (1, "a"), (2, "b")];
+        let inline: HybridMap<i32, &str, 4> = entries.into_iter().collect();
+        let spilled: HybridMap<i32, &str, 2> = entries.into_iter().collect();
+        assert!(spilled.spilled());
+        let expected = r#"{1: "a", 2: "b", 3: "c"}"#;
+        assert_eq!(format!("{:?}", inline.debug_sorted()), expected);
+        assert_eq!(format!("{:?}", spilled.debug_sorted()), expected);
+    }
+
+    #[test]
+    fn test_into_sorted_iter() {
+        for len in [4, 10] {
+            let map: HybridMap<i32, String, 4> =
+                (0..len).rev().map(|i| (i, i.to_string())).collect();
+            let expected: Vec<(i32, String)> = (0..len).map(|i| (i, i.to_string())).collect();
+            assert_eq!(map.into_sorted_iter().collect::<Vec<_>>(), expected);
         }
-        for i in 0..1000 {
-            assert_eq!(map.get(&i), Some(&(i * 10)));
+    }
+
+    #[test]
+    fn test_swap_values() {
+        for len in [3, 10] {
+            let mut map: HybridMap<i32, i32, 4> = (0..len).map(|i| (i, i * 10)).collect();
+            assert!(map.swap_values(&0, &2));
+            assert_eq!(map.get(&0), Some(&20));
+            assert_eq!(map.get(&2), Some(&0));
+
+            assert!(!map.swap_values(&1, &1));
+            assert!(!map.swap_values(&1, &100));
+            assert!(!map.swap_values(&100, &1));
+            assert_eq!(map.get(&1), Some(&10));
         }
     }
 
     #[test]
-    pub fn test_len() {
-        let mut map = HybridMap::<i32, i32, 3>::new();
-        assert_eq!(map.len(), 0);
-        map.insert(1, 10);
-        map.insert(2, 20);
-        map.insert(3, 30);
+    fn test_remove_by_value() {
+        let mut map: HybridMap<i32, i32, 4> = (0..3).map(|i| (i, i % 2)).collect();
+        assert_eq!(map.remove_by_value(&0), 2);
+        assert_eq!(map.remove_by_value(&7), 0);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&1), Some(&1));
+
+        let mut map: HybridMap<i32, i32, 4> = (0..10).map(|i| (i, i % 3)).collect();
+        assert_eq!(map.remove_by_value(&7), 0);
+        assert!(map.spilled());
+        assert_eq!(map.remove_by_value(&1), 3);
+        assert!(map.spilled());
+        assert_eq!(map.remove_by_value(&0), 4);
         assert_eq!(map.len(), 3);
-        map.insert(4, 40);
-        assert_eq!(map.len(), 4);
+        assert!(map.values().all(|v| *v == 2));
+        assert_eq!(map.remove_by_value(&2), 3);
+        assert!(map.is_inline());
+        assert!(map.is_empty());
     }
 
     #[test]
-    pub fn test_default() {
-        let map = HybridMap::<i32, i32, 3>::default();
-        assert_eq!(map.len(), 0);
+    fn test_hash_of() {
+        let mut map = HybridMap::<&str, i32, 2>::new();
+        map.insert("a", 1);
+        let inline_hash = map.hash_of("a");
+        assert_eq!(inline_hash, map.hasher().hash_one("a"));
+        map.insert("b", 2);
+        map.insert("c", 3);
+        assert!(map.spilled());
+        assert_eq!(map.hash_of("a"), inline_hash);
     }
 
     #[test]
-    pub fn test_clone() {
-        let mut map = HybridMap::<i32, i32, 3>::new();
-        map.insert(1, 10);
-        map.insert(2, 20);
-        map.insert(3, 30);
-        let map2 = map.clone();
-        assert_eq!(map2.len(), 3);
-        assert_eq!(map2.get(&1), Some(&10));
-        assert_eq!(map2.get(&2), Some(&20));
-        assert_eq!(map2.get(&3), Some(&30));
-        map.insert(4, 40);
-        assert_eq!(map2.len(), 3);
-        let map3 = map.clone();
-        assert_eq!(map3.len(), 4);
+    fn test_map_values() {
+        for len in [3, 10] {
+            let map: HybridMap<i32, i32, 4> = (0..len).map(|i| (i, i * 10)).collect();
+            let strings = map.map_values(|v| v.to_string());
+            assert_eq!(strings.is_inline(), map.is_inline());
+            assert_eq!(strings.len(), map.len());
+            for i in 0..len {
+                assert_eq!(strings.get(&i), Some(&(i * 10).to_string()));
+            }
+        }
     }
 
     #[test]
-    fn test_is_empty() {
+    fn test_iter_len() {
         let mut map = HybridMap::<i32, i32, 3>::new();
-        assert!(map.is_empty());
         map.insert(1, 10);
-        assert!(!map.is_empty());
         map.insert(2, 20);
+        assert!(map.is_inline());
+        assert_eq!(map.iter().len(), map.len());
+        assert_eq!(map.iter_mut().len(), 2);
+        let mut iter = map.iter();
+        iter.next();
+        assert_eq!(iter.len(), 1);
+        assert_eq!(map.clone().into_iter().len(), 2);
+
         map.insert(3, 30);
         map.insert(4, 40);
-        assert!(!map.is_empty());
+        assert!(map.spilled());
+        assert_eq!(map.iter().len(), map.len());
+        assert_eq!(map.iter_mut().len(), 4);
+        let mut iter = map.iter();
+        iter.next();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(map.into_iter().len(), 4);
     }
 
     #[test]
-    fn test_get_mut() {
-        let mut map = HybridMap::<i32, i32, 3>::new();
-        map.insert(1, 10);
-        assert_eq!(map.get_mut(&1), Some(&mut 10));
-        let entry = map.get_mut(&1).unwrap();
-        *entry = 20;
-        assert_eq!(map.get(&1), Some(&20));
+    fn test_iter_clone() {
+        // A key type that is not Clone.
+        #[derive(PartialEq, Eq, Hash, Debug)]
+        struct Key(i32);
+
+        let mut map = HybridMap::<Key, i32, 3>::new();
+        map.insert(Key(1), 10);
+        map.insert(Key(2), 20);
+        map.insert(Key(3), 30);
+        let mut iter = map.iter();
+        assert_eq!(iter.next(), Some((&Key(1), &10)));
+        let mut snapshot = iter.clone();
+        assert_eq!(iter.next(), Some((&Key(2), &20)));
+        assert_eq!(iter.next(), Some((&Key(3), &30)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(snapshot.next(), Some((&Key(2), &20)));
+        assert_eq!(snapshot.len(), 1);
+
+        map.insert(Key(4), 40);
+        let mut iter = map.iter();
+        iter.next();
+        let snapshot = iter.clone();
+        let rest: Vec<_> = iter.collect();
+        let rest_snapshot: Vec<_> = snapshot.collect();
+        assert_eq!(rest.len(), 3);
+        assert_eq!(rest, rest_snapshot);
+    }
 
+    #[test]
+    fn test_iter_size_hint() {
         let mut map = HybridMap::<i32, i32, 3>::new();
-        map.insert(1, 10);
-        map.insert(2, 20);
-        map.insert(3, 30);
-        map.insert(4, 40);
-        let entry = map.get_mut(&1).unwrap();
-        *entry = 30;
-        assert_eq!(map.get(&1), Some(&30));
+        for i in 0..6 {
+            let len = map.len();
+            assert_eq!(map.iter().size_hint(), (len, Some(len)));
+            assert_eq!(map.iter_mut().size_hint(), (len, Some(len)));
+            assert_eq!(map.clone().into_iter().size_hint(), (len, Some(len)));
+            map.insert(i, i * 10);
+        }
+        assert!(map.spilled());
+        assert_eq!(map.iter().size_hint(), (6, Some(6)));
+        assert_eq!(map.iter_mut().size_hint(), (6, Some(6)));
+        assert_eq!(map.into_iter().size_hint(), (6, Some(6)));
     }
 
     #[test]
-    fn test_remove_entry() {
+    fn test_iter_fused() {
         let mut map = HybridMap::<i32, i32, 3>::new();
         map.insert(1, 10);
+        let mut iter = map.iter();
+        assert!(iter.next().is_some());
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+
         map.insert(2, 20);
         map.insert(3, 30);
-        assert_eq!(map.remove_entry(&2), Some((2, 20)));
-        assert!(map.len() == 2);
-        assert_eq!(map.get(&2), None);
-        assert_eq!(map.remove_entry(&2), None);
         map.insert(4, 40);
-        map.insert(5, 50);
-        assert_eq!(map.remove_entry(&12), None);
-        assert_eq!(map.remove_entry(&3), Some((3, 30)));
-        assert!(map.len() == 3);
+        let mut iter = map.iter_mut();
+        assert_eq!(iter.by_ref().count(), 4);
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+
+        let mut iter = map.into_iter();
+        assert_eq!(iter.by_ref().count(), 4);
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
     }
 
     #[test]
-    fn test_remove() {
+    fn test_iter_rev() {
         let mut map = HybridMap::<i32, i32, 3>::new();
         map.insert(1, 10);
         map.insert(2, 20);
         map.insert(3, 30);
-        assert_eq!(map.remove(&2), Some(20));
-        assert!(map.len() == 2);
-        assert_eq!(map.get(&2), None);
-        assert_eq!(map.remove(&2), None);
+        let rev: Vec<_> = map.iter().rev().collect();
+        assert_eq!(rev, vec![(&3, &30), (&2, &20), (&1, &10)]);
+
+        let mut iter = map.iter();
+        assert_eq!(iter.next(), Some((&1, &10)));
+        assert_eq!(iter.next_back(), Some((&3, &30)));
+        assert_eq!(iter.next(), Some((&2, &20)));
+        assert_eq!(iter.next_back(), None);
+
+        for (i, (_, v)) in map.iter_mut().rev().enumerate() {
+            *v += i as i32;
+        }
+        assert_eq!(map.get(&3), Some(&30));
+        assert_eq!(map.get(&2), Some(&21));
+        assert_eq!(map.get(&1), Some(&12));
+
+        // Spilled maps still yield every element once.
         map.insert(4, 40);
-        map.insert(5, 50);
-        assert_eq!(map.remove(&12), None);
-        assert_eq!(map.remove(&3), Some(30));
-        assert!(map.len() == 3);
+        assert_eq!(map.iter().rev().count(), 4);
+        assert_eq!(map.iter_mut().rev().count(), 4);
     }
 
     #[test]
-    fn test_clear() {
+    fn test_keys() {
         let mut map = HybridMap::<i32, i32, 3>::new();
         map.insert(1, 10);
-        map.clear();
-        assert!(map.is_empty());
-        assert_eq!(map.get(&1), None);
         map.insert(2, 20);
+        let keys: Vec<_> = map.keys().collect();
+        assert_eq!(keys, vec![&1, &2]);
+        assert_eq!(map.keys().size_hint(), (2, Some(2)));
+
         map.insert(3, 30);
         map.insert(4, 40);
-        map.insert(5, 50);
-        assert_eq!(map.len(), 4);
-        map.clear();
-        assert!(map.is_empty());
-        assert_eq!(map.get(&4), None);
+        let mut keys: Vec<_> = map.keys().copied().collect();
+        keys.sort();
+        assert_eq!(keys, vec![1, 2, 3, 4]);
+        assert_eq!(map.keys().size_hint(), (4, Some(4)));
     }
 
     #[test]
-    fn test_iter() {
+    fn test_values() {
         let mut map = HybridMap::<i32, i32, 3>::new();
         map.insert(1, 10);
         map.insert(2, 20);
+        let values: Vec<_> = map.values().collect();
+        assert_eq!(values, vec![&10, &20]);
+
         map.insert(3, 30);
-        let mut iter = map.iter();
-        // Ordered
-        assert_eq!(iter.next(), Some((&1, &10)));
-        assert_eq!(iter.next(), Some((&2, &20)));
-        assert_eq!(iter.next(), Some((&3, &30)));
-        assert_eq!(iter.next(), None);
         map.insert(4, 40);
-        let iter = map.iter();
-        let vec = iter.collect::<Vec<_>>();
-        assert_eq!(vec.len(), 4);
+        let mut values: Vec<_> = map.values().copied().collect();
+        values.sort();
+        assert_eq!(values, vec![10, 20, 30, 40]);
+        assert_eq!(map.values().size_hint(), (4, Some(4)));
     }
 
     #[test]
-    fn test_iter_mut() {
-        // Add +1
+    fn test_values_mut() {
         let mut map = HybridMap::<i32, i32, 3>::new();
         map.insert(1, 10);
         map.insert(2, 20);
-        map.insert(3, 30);
-        for (_, v) in map.iter_mut() {
+        for v in map.values_mut() {
             *v += 1;
         }
         assert_eq!(map.get(&1), Some(&11));
         assert_eq!(map.get(&2), Some(&21));
-        assert_eq!(map.get(&3), Some(&31));
 
+        map.insert(3, 30);
         map.insert(4, 40);
-        for (_, v) in map.iter_mut() {
+        assert_eq!(map.values_mut().size_hint(), (4, Some(4)));
+        for v in map.values_mut() {
             *v += 1;
         }
-
         assert_eq!(map.get(&1), Some(&12));
         assert_eq!(map.get(&4), Some(&41));
     }
 
+    #[test]
+    fn test_into_keys() {
+        let mut map = HybridMap::<i32, String, 3>::new();
+        map.insert(1, "one".to_string());
+        map.insert(2, "two".to_string());
+        let keys: Vec<_> = map.clone().into_keys().collect();
+        assert_eq!(keys, vec![1, 2]);
+
+        map.insert(3, "three".to_string());
+        map.insert(4, "four".to_string());
+        let mut keys: Vec<_> = map.into_keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_into_values() {
+        let mut map = HybridMap::<i32, String, 3>::new();
+        map.insert(1, "one".to_string());
+        map.insert(2, "two".to_string());
+        let values: Vec<_> = map.clone().into_values().collect();
+        assert_eq!(values, vec!["one".to_string(), "two".to_string()]);
+
+        map.insert(3, "three".to_string());
+        map.insert(4, "four".to_string());
+        let mut values: Vec<_> = map.into_values().collect();
+        values.sort();
+        assert_eq!(values, vec!["four", "one", "three", "two"]);
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let map: HybridMap<i32, i32, 3> = (1..=3).map(|i| (i, i * 10)).collect();
+        assert!(matches!(map.inner, InnerContainer::Vec(..)));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&2), Some(&20));
+
+        let map: HybridMap<i32, i32, 3> = (1..=10).map(|i| (i, i * 10)).collect();
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+        assert_eq!(map.len(), 10);
+        assert_eq!(map.get(&10), Some(&100));
+
+        // The last value wins
+        let map: HybridMap<i32, i32, 3> = vec![(1, 10), (2, 20), (1, 11)].into_iter().collect();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&11));
+    }
+
+    #[test]
+    fn test_from_array() {
+        let map = HybridMap::<i32, &str, 3>::from([(1, "a"), (2, "b")]);
+        assert!(matches!(map.inner, InnerContainer::Vec(..)));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&"a"));
+        assert_eq!(map.get(&2), Some(&"b"));
+
+        let map = HybridMap::<i32, &str, 3>::from([(1, "a"), (2, "b"), (3, "c"), (4, "d")]);
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(&4), Some(&"d"));
+
+        // The last value wins
+        let map = HybridMap::<i32, &str, 3>::from([(1, "a"), (1, "b")]);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&1), Some(&"b"));
+    }
+
+    #[test]
+    fn test_from_hashmap() {
+        let hashmap: HashMap<i32, i32> = (1..=3).map(|i| (i, i * 10)).collect();
+        let map = HybridMap::<i32, i32, 3>::from(hashmap);
+        assert!(matches!(map.inner, InnerContainer::Vec(..)));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&3), Some(&30));
+
+        let hashmap: HashMap<i32, i32> = (1..=4).map(|i| (i, i * 10)).collect();
+        let map = HybridMap::<i32, i32, 3>::from(hashmap);
+        assert!(matches!(map.inner, InnerContainer::HashMap(_)));
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(&4), Some(&40));
+    }
+
+    #[test]
+    fn test_into_hashmap() {
+        let map: HybridMap<i32, i32, 3> = (1..=3).map(|i| (i, i * 10)).collect();
+        let hashmap: HashMap<i32, i32> = map.into();
+        assert_eq!(hashmap.len(), 3);
+        assert_eq!(hashmap.get(&3), Some(&30));
+
+        let map: HybridMap<i32, i32, 3> = (1..=4).map(|i| (i, i * 10)).collect();
+        let hashmap: HashMap<i32, i32> = map.into();
+        assert_eq!(hashmap.len(), 4);
+        assert_eq!(hashmap.get(&4), Some(&40));
+
+        let map: HybridMap<i32, i32, 3> = (1..=2).map(|i| (i, i * 10)).collect();
+        assert_eq!(map.into_hashmap().get(&2), Some(&20));
+        let map: HybridMap<i32, i32, 3> = (1..=4).map(|i| (i, i * 10)).collect();
+        assert_eq!(map.into_hashmap().get(&4), Some(&40));
+    }
+
+    #[test]
+    fn test_eq() {
+        let mut inline = HybridMap::<i32, i32, 4>::new();
+        let mut spilled = HybridMap::<i32, i32, 4>::with_capacity(5);
+        for i in 0..4 {
+            inline.insert(i, i * 10);
+            spilled.insert(3 - i, (3 - i) * 10);
+        }
+        assert!(matches!(inline.inner, InnerContainer::Vec(..)));
+        assert!(matches!(spilled.inner, InnerContainer::HashMap(_)));
+        assert_eq!(inline, spilled);
+        assert_eq!(spilled, inline);
+
+        spilled.insert(0, 1);
+        assert_ne!(inline, spilled);
+        spilled.insert(0, 0);
+        inline.remove(&0);
+        assert_ne!(inline, spilled);
+        assert_ne!(spilled, inline);
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct TestHasher {
+        id: u64,
+    }
+
+    impl BuildHasher for TestHasher {
+        type Hasher = std::collections::hash_map::DefaultHasher;
+
+        fn build_hasher(&self) -> Self::Hasher {
+            Self::Hasher::new()
+        }
+    }
+
+    #[test]
+    fn test_custom_hasher() {
+        let mut map = HybridMap::<i32, i32, 3, TestHasher>::with_hasher(TestHasher { id: 42 });
+        for i in 0..3 {
+            map.insert(i, i * 10);
+        }
+        assert!(map.is_inline());
+        assert_eq!(map.hasher().id, 42);
+
+        // The hasher is kept when switching to the HashMap, and back
+        map.insert(3, 30);
+        assert!(map.spilled());
+        assert_eq!(map.hasher().id, 42);
+        assert_eq!(map.get(&3), Some(&30));
+        map.retain(|k, _| *k == 0);
+        assert!(map.is_inline());
+        assert_eq!(map.hasher().id, 42);
+
+        let map = HybridMap::<i32, i32, 3, TestHasher>::with_capacity_and_hasher(
+            10,
+            TestHasher { id: 7 },
+        );
+        assert!(map.spilled());
+        assert_eq!(map.hasher().id, 7);
+
+        let map: HybridMap<i32, i32, 3, TestHasher> = (0..10).map(|i| (i, i)).collect();
+        assert_eq!(map.len(), 10);
+        assert_eq!(map.hasher().id, 0);
+    }
+
     #[test]
     fn test_into_iter() {
         let mut map = HybridMap::<i32, i32, 3>::new();
@@ -0,0 +1,438 @@
+//! A `HybridMap` variant preserving the insertion order, using `IndexMap`.
+use crate::DefaultHashBuilder;
+use core::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash},
+    iter::FusedIterator,
+};
+use indexmap::IndexMap;
+use smallvec::SmallVec;
+
+#[derive(Clone, Debug)]
+enum OrderedContainer<K, V, const N: usize, S> {
+    Vec(SmallVec<(K, V), N>, S),
+    // Keeps the insertion order once the map has switched from the Vec.
+    IndexMap(IndexMap<K, V, S>),
+}
+
+/// A hybrid map that iterates in insertion order, even after switching
+/// from the `Vec` to a hash table.
+///
+/// Once spilled, the entries are stored in an `IndexMap`, which keeps them in
+/// a vector next to a hash table of indices. This costs an index and a hash
+/// per entry on top of a `HashMap`, about 16 bytes on 64-bit targets. Removing
+/// an entry shifts the following ones to preserve the order, in O(n).
+///
+/// # Examples
+///
+/// ```
+/// use hybridmap::HybridOrderedMap;
+///
+/// let mut map = HybridOrderedMap::<i32, &str, 2>::new();
+/// map.insert(3, "three");
+/// map.insert(1, "one");
+/// map.insert(2, "two");
+///
+/// assert!(map.spilled());
+/// let keys: Vec<_> = map.iter().map(|(k, _)| *k).collect();
+/// assert_eq!(keys, [3, 1, 2]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HybridOrderedMap<K, V, const N: usize = 16, S = DefaultHashBuilder> {
+    inner: OrderedContainer<K, V, N, S>,
+    shrink_threshold: usize,
+}
+
+// Default trait.
+impl<K, V, const N: usize, S> Default for HybridOrderedMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    S: Default,
+{
+    fn default() -> Self {
+        Self {
+            inner: OrderedContainer::Vec(SmallVec::new(), S::default()),
+            shrink_threshold: N / 2,
+        }
+    }
+}
+
+// FromIterator trait.
+impl<K, V, const N: usize, S> FromIterator<(K, V)> for HybridOrderedMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<K, V, const N: usize> HybridOrderedMap<K, V, N, DefaultHashBuilder>
+where
+    K: Eq + Hash,
+{
+    /// Creates an empty `HybridOrderedMap`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_hasher(DefaultHashBuilder::default())
+    }
+}
+
+impl<K, V, const N: usize, S> HybridOrderedMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Creates an empty `HybridOrderedMap` which will use the given hash
+    /// builder once it switches to an `IndexMap`.
+    #[inline]
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            inner: OrderedContainer::Vec(SmallVec::new(), hash_builder),
+            shrink_threshold: N / 2,
+        }
+    }
+
+    /// Sets the length at which the `IndexMap` switches back to a `Vec`
+    /// after removals, `N / 2` by default.
+    ///
+    /// As with [`HybridMap::with_shrink_threshold`](crate::HybridMap::with_shrink_threshold),
+    /// the threshold is capped at `N`.
+    #[inline]
+    pub fn with_shrink_threshold(mut self, threshold: usize) -> Self {
+        self.shrink_threshold = threshold.min(N);
+        self.shrink_after_removal();
+        self
+    }
+
+    /// Returns the length at which the `IndexMap` switches back to a `Vec`.
+    #[inline]
+    pub fn shrink_threshold(&self) -> usize {
+        self.shrink_threshold
+    }
+
+    /// Returns the number of elements in the map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match &self.inner {
+            OrderedContainer::Vec(vec, _) => vec.len(),
+            OrderedContainer::IndexMap(map) => map.len(),
+        }
+    }
+
+    /// Returns `true` if the map contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the elements are currently stored inline in a `Vec`.
+    #[inline]
+    pub fn is_inline(&self) -> bool {
+        matches!(self.inner, OrderedContainer::Vec(..))
+    }
+
+    /// Returns `true` if the map has switched to an `IndexMap`.
+    #[inline]
+    pub fn spilled(&self) -> bool {
+        !self.is_inline()
+    }
+
+    /// Get a reference to the value of the given key.
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match &self.inner {
+            OrderedContainer::Vec(vec, _) => {
+                vec.iter()
+                    .find_map(|(k, v)| if k.borrow() == key { Some(v) } else { None })
+            }
+            OrderedContainer::IndexMap(map) => map.get(key),
+        }
+    }
+
+    /// Returns `true` if the map contains the given key.
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Get a mutable reference to the value of the given key.
+    #[inline]
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match &mut self.inner {
+            OrderedContainer::Vec(vec, _) => {
+                vec.iter_mut()
+                    .find_map(|(k, v)| if (*k).borrow() == key { Some(v) } else { None })
+            }
+            OrderedContainer::IndexMap(map) => map.get_mut(key),
+        }
+    }
+
+    /// Insert an element into the map.
+    ///
+    /// A new key goes after all the others, while updating the value of an
+    /// existing key keeps its position. Returns the previous value if the key
+    /// was already present.
+    #[inline]
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match &mut self.inner {
+            OrderedContainer::Vec(vec, hash_builder) => {
+                if let Some((_, v)) = vec.iter_mut().find(|(k, _)| *k == key) {
+                    return Some(core::mem::replace(v, value));
+                }
+                if vec.len() < N {
+                    vec.push((key, value));
+                } else {
+                    let mut map =
+                        IndexMap::with_capacity_and_hasher(vec.len() + 1, hash_builder.clone());
+                    map.extend(vec.drain(..));
+                    map.insert(key, value);
+                    self.inner = OrderedContainer::IndexMap(map);
+                }
+                None
+            }
+            OrderedContainer::IndexMap(map) => map.insert(key, value),
+        }
+    }
+
+    /// Remove an entry from the map by its key, returning its value.
+    ///
+    /// The map switches back to a `Vec` when the `IndexMap` gets down to
+    /// the shrink threshold, `N / 2` elements by default, in the same order.
+    #[inline]
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match &mut self.inner {
+            OrderedContainer::Vec(vec, _) => vec
+                .iter()
+                .position(|(k, _)| k.borrow() == key)
+                .map(|index| vec.remove(index).1),
+            OrderedContainer::IndexMap(map) => {
+                let value = map.shift_remove(key);
+                self.shrink_after_removal();
+                value
+            }
+        }
+    }
+
+    /// Switch back to a `Vec` if the `IndexMap` is down to the shrink
+    /// threshold, keeping the order.
+    #[inline]
+    fn shrink_after_removal(&mut self) {
+        if let OrderedContainer::IndexMap(map) = &mut self.inner {
+            if map.len() <= self.shrink_threshold {
+                let hash_builder = map.hasher().clone();
+                self.inner = OrderedContainer::Vec(map.drain(..).collect(), hash_builder);
+            }
+        }
+    }
+
+    /// Clear the map, removing all elements.
+    #[inline]
+    pub fn clear(&mut self) {
+        match &mut self.inner {
+            OrderedContainer::Vec(vec, _) => vec.clear(),
+            OrderedContainer::IndexMap(map) => map.clear(),
+        }
+    }
+
+    /// Returns an iterator over the entries of the map, in insertion order.
+    #[inline]
+    pub fn iter(&self) -> OrderedIter<'_, K, V> {
+        match &self.inner {
+            OrderedContainer::Vec(vec, _) => OrderedIter::Vec(vec.iter()),
+            OrderedContainer::IndexMap(map) => OrderedIter::IndexMap(map.iter()),
+        }
+    }
+}
+
+impl<'a, K, V, const N: usize, S> IntoIterator for &'a HybridOrderedMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = OrderedIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a `HybridOrderedMap`, in insertion order.
+pub enum OrderedIter<'a, K, V> {
+    Vec(core::slice::Iter<'a, (K, V)>),
+    IndexMap(indexmap::map::Iter<'a, K, V>),
+}
+
+impl<'a, K, V> Iterator for OrderedIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            OrderedIter::Vec(iter) => iter.next().map(|(k, v)| (k, v)),
+            OrderedIter::IndexMap(iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            OrderedIter::Vec(iter) => iter.size_hint(),
+            OrderedIter::IndexMap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<K, V> DoubleEndedIterator for OrderedIter<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            OrderedIter::Vec(iter) => iter.next_back().map(|(k, v)| (k, v)),
+            OrderedIter::IndexMap(iter) => iter.next_back(),
+        }
+    }
+}
+
+impl<K, V> Clone for OrderedIter<'_, K, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        match self {
+            OrderedIter::Vec(iter) => OrderedIter::Vec(iter.clone()),
+            OrderedIter::IndexMap(iter) => OrderedIter::IndexMap(iter.clone()),
+        }
+    }
+}
+
+impl<K, V> ExactSizeIterator for OrderedIter<'_, K, V> {}
+
+impl<K, V> FusedIterator for OrderedIter<'_, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys<const N: usize>(map: &HybridOrderedMap<i32, i32, N>) -> Vec<i32> {
+        map.iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn test_ordered_across_spill() {
+        let mut map = HybridOrderedMap::<i32, i32, 4>::new();
+        for key in [5, 3, 9, 1] {
+            assert_eq!(map.insert(key, key * 10), None);
+        }
+        assert!(map.is_inline());
+        assert_eq!(keys(&map), [5, 3, 9, 1]);
+
+        for key in [7, 0, 4] {
+            map.insert(key, key * 10);
+        }
+        assert!(map.spilled());
+        assert_eq!(keys(&map), [5, 3, 9, 1, 7, 0, 4]);
+
+        // Updating a value keeps its position.
+        assert_eq!(map.insert(3, 33), Some(30));
+        assert_eq!(keys(&map), [5, 3, 9, 1, 7, 0, 4]);
+        assert_eq!(map.get(&3), Some(&33));
+        *map.get_mut(&0).unwrap() += 1;
+        assert_eq!(map.get(&0), Some(&1));
+        assert_eq!(map.iter().len(), 7);
+        assert_eq!(map.iter().next_back(), Some((&4, &40)));
+    }
+
+    #[test]
+    fn test_ordered_remove_shrinks_in_order() {
+        let mut map: HybridOrderedMap<i32, i32, 4> = (0..8).rev().map(|i| (i, i)).collect();
+        assert!(map.spilled());
+        assert_eq!(map.remove(&5), Some(5));
+        assert_eq!(map.remove(&5), None);
+        assert_eq!(keys(&map), [7, 6, 4, 3, 2, 1, 0]);
+        for key in [6, 3, 1, 0] {
+            map.remove(&key);
+        }
+        assert!(map.spilled());
+        map.remove(&4);
+        assert!(map.is_inline());
+        assert_eq!(keys(&map), [7, 2]);
+
+        map.insert(9, 9);
+        assert_eq!(keys(&map), [7, 2, 9]);
+        assert_eq!(map.remove(&7), Some(7));
+        assert_eq!(keys(&map), [2, 9]);
+        assert!(map.contains_key(&9));
+
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_ordered_shrink_threshold() {
+        let map: HybridOrderedMap<i32, i32, 4> = (0..6).map(|i| (i, i)).collect();
+        assert_eq!(map.shrink_threshold(), 2);
+        let mut map = map.with_shrink_threshold(1);
+        assert_eq!(map.shrink_threshold(), 1);
+        map.remove(&0);
+        map.remove(&1);
+        map.remove(&2);
+        map.remove(&3);
+        assert!(map.spilled());
+        map.remove(&4);
+        assert!(map.is_inline());
+        assert_eq!(keys(&map), [5]);
+
+        // Raising the threshold switches back right away, in order.
+        let map: HybridOrderedMap<i32, i32, 4> = (0..5).rev().map(|i| (i, i)).collect();
+        let mut map = map.with_shrink_threshold(0);
+        map.remove(&2);
+        assert!(map.spilled());
+        let map = map.with_shrink_threshold(10);
+        assert_eq!(map.shrink_threshold(), 4);
+        assert!(map.is_inline());
+        assert_eq!(keys(&map), [4, 3, 1, 0]);
+    }
+
+    #[test]
+    fn test_ordered_iter_clone() {
+        // A key type that is not Clone.
+        #[derive(PartialEq, Eq, Hash, Debug)]
+        struct Key(i32);
+
+        let mut map = HybridOrderedMap::<Key, i32, 3>::new();
+        for key in [3, 1, 2] {
+            map.insert(Key(key), key * 10);
+        }
+        for spilled in [false, true] {
+            assert_eq!(map.spilled(), spilled);
+            let mut iter = map.iter();
+            assert_eq!(iter.next(), Some((&Key(3), &30)));
+            let snapshot = iter.clone();
+            assert_eq!(iter.next(), Some((&Key(1), &10)));
+            let rest: Vec<_> = snapshot.collect();
+            assert_eq!(rest[..2], [(&Key(1), &10), (&Key(2), &20)]);
+            assert_eq!(rest.len(), map.len() - 1);
+            map.insert(Key(0), 0);
+        }
+    }
+}
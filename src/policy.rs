@@ -0,0 +1,57 @@
+//! Policies deciding when a `HybridMap` switches from the `Vec` to a `HashMap`.
+
+/// Decides when the inline `Vec` of a `HybridMap` switches to a `HashMap`.
+///
+/// A policy is given to [`with_spill_policy`](crate::HybridMap::with_spill_policy)
+/// as a `'static` reference, so it can hold parameters such as a byte budget
+/// in a `static` or a constant.
+///
+/// # Examples
+///
+/// ```
+/// use hybridmap::{HybridMap, SpillPolicy};
+///
+/// // Spills once the entries would take more than 64 bytes.
+/// struct ByteBudget {
+///     budget: usize,
+///     entry_size: usize,
+/// }
+///
+/// impl SpillPolicy for ByteBudget {
+///     fn should_spill(&self, current_len: usize, _n: usize) -> bool {
+///         (current_len + 1) * self.entry_size > self.budget
+///     }
+/// }
+///
+/// static BUDGET: ByteBudget = ByteBudget {
+///     budget: 64,
+///     entry_size: std::mem::size_of::<(u64, u64)>(),
+/// };
+///
+/// let mut map = HybridMap::<u64, u64, 16>::new().with_spill_policy(&BUDGET);
+/// for i in 0..4 {
+///     map.insert(i, i);
+/// }
+/// assert!(map.is_inline());
+/// map.insert(4, 4);
+/// assert!(map.spilled());
+/// ```
+pub trait SpillPolicy: Sync {
+    /// Returns `true` if inserting a new key into a `Vec` holding
+    /// `current_len` entries should switch the map to a `HashMap`.
+    ///
+    /// `n` is the inline capacity of the map. The map always switches once
+    /// the `Vec` holds `n` entries, so a policy can only spill earlier.
+    fn should_spill(&self, current_len: usize, n: usize) -> bool;
+}
+
+/// The default policy, switching to a `HashMap` above `N` entries.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CountPolicy;
+
+impl SpillPolicy for CountPolicy {
+    #[inline]
+    fn should_spill(&self, current_len: usize, n: usize) -> bool {
+        current_len >= n
+    }
+}
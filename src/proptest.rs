@@ -0,0 +1,52 @@
+//! Proptest support for `HybridMap`, enabled with the `proptest` feature.
+use crate::HybridMap;
+use core::hash::Hash;
+use proptest::{
+    arbitrary::{any_with, Arbitrary, StrategyFor},
+    collection::{vec, SizeRange, VecStrategy},
+    strategy::{Map, Strategy},
+};
+
+/// Generates maps from random pairs, so depending on the number of pairs
+/// and duplicate keys, the map may be inline or spilled.
+///
+/// The parameters are the size range of the generated pairs, and the
+/// parameters of the keys and values.
+impl<K, V, const N: usize> Arbitrary for HybridMap<K, V, N>
+where
+    K: Arbitrary + Eq + Hash,
+    V: Arbitrary,
+{
+    type Parameters = (SizeRange, K::Parameters, V::Parameters);
+    type Strategy = Map<VecStrategy<(StrategyFor<K>, StrategyFor<V>)>, fn(Vec<(K, V)>) -> Self>;
+
+    fn arbitrary_with((size, key_args, value_args): Self::Parameters) -> Self::Strategy {
+        vec((any_with::<K>(key_args), any_with::<V>(value_args)), size)
+            .prop_map(|pairs| pairs.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    proptest! {
+        #[test]
+        fn test_arbitrary_roundtrip(map in any::<HybridMap<i8, i32, 8>>()) {
+            let hash_map: HashMap<i8, i32> = map.iter().map(|(k, v)| (*k, *v)).collect();
+            prop_assert_eq!(hash_map.len(), map.len());
+            let back: HybridMap<i8, i32, 8> = hash_map.into_iter().collect();
+            prop_assert_eq!(back, map);
+        }
+
+        #[test]
+        fn test_arbitrary_bounded_size(
+            map in any_with::<HybridMap<u8, u8, 8>>(((0..=8).into(), (), ()))
+        ) {
+            prop_assert!(map.len() <= 8);
+            prop_assert!(map.is_inline());
+        }
+    }
+}
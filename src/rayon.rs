@@ -0,0 +1,164 @@
+//! Rayon support for `HybridMap`, enabled with the `rayon` feature.
+use crate::{HybridMap, InnerContainer};
+use core::hash::{BuildHasher, Hash};
+use rayon::iter::{
+    plumbing::UnindexedConsumer, IntoParallelIterator, IntoParallelRefIterator,
+    IntoParallelRefMutIterator, ParallelIterator,
+};
+
+impl<K, V, const N: usize, S> IntoParallelIterator for HybridMap<K, V, N, S>
+where
+    K: Eq + Hash + Send,
+    V: Send,
+    S: BuildHasher,
+{
+    type Item = (K, V);
+    type Iter = IntoParIter<K, V>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        match self.inner {
+            InnerContainer::Vec(vec, _) => IntoParIter::Vec(Vec::from(vec).into_par_iter()),
+            InnerContainer::HashMap(map) => IntoParIter::HashMap(map.into_par_iter()),
+        }
+    }
+}
+
+impl<'a, K, V, const N: usize, S> IntoParallelIterator for &'a HybridMap<K, V, N, S>
+where
+    K: Eq + Hash + Sync,
+    V: Sync,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V);
+    type Iter = ParIter<'a, K, V>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        match &self.inner {
+            InnerContainer::Vec(vec, _) => ParIter::Vec(vec.par_iter()),
+            InnerContainer::HashMap(map) => ParIter::HashMap(map.par_iter()),
+        }
+    }
+}
+
+impl<'a, K, V, const N: usize, S> IntoParallelIterator for &'a mut HybridMap<K, V, N, S>
+where
+    K: Eq + Hash + Send + Sync,
+    V: Send,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a mut V);
+    type Iter = ParIterMut<'a, K, V>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        match &mut self.inner {
+            InnerContainer::Vec(vec, _) => ParIterMut::Vec(vec.par_iter_mut()),
+            InnerContainer::HashMap(map) => ParIterMut::HashMap(map.par_iter_mut()),
+        }
+    }
+}
+
+/// A parallel iterator over the entries of a `HybridMap`.
+pub enum ParIter<'a, K, V> {
+    Vec(rayon::slice::Iter<'a, (K, V)>),
+    HashMap(rayon::collections::hash_map::Iter<'a, K, V>),
+}
+
+impl<'a, K: Sync, V: Sync> ParallelIterator for ParIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        match self {
+            ParIter::Vec(iter) => iter.map(|(k, v)| (k, v)).drive_unindexed(consumer),
+            ParIter::HashMap(iter) => iter.drive_unindexed(consumer),
+        }
+    }
+}
+
+/// A mutable parallel iterator over the entries of a `HybridMap`.
+pub enum ParIterMut<'a, K, V> {
+    Vec(rayon::slice::IterMut<'a, (K, V)>),
+    HashMap(rayon::collections::hash_map::IterMut<'a, K, V>),
+}
+
+impl<'a, K: Send + Sync, V: Send> ParallelIterator for ParIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        match self {
+            ParIterMut::Vec(iter) => iter
+                .map(|(ref k, ref mut v)| (k, v))
+                .drive_unindexed(consumer),
+            ParIterMut::HashMap(iter) => iter.drive_unindexed(consumer),
+        }
+    }
+}
+
+/// A consuming parallel iterator over the entries of a `HybridMap`.
+pub enum IntoParIter<K, V> {
+    Vec(rayon::vec::IntoIter<(K, V)>),
+    HashMap(rayon::collections::hash_map::IntoIter<K, V>),
+}
+
+impl<K: Send, V: Send> ParallelIterator for IntoParIter<K, V> {
+    type Item = (K, V);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        match self {
+            IntoParIter::Vec(iter) => iter.drive_unindexed(consumer),
+            IntoParIter::HashMap(iter) => iter.drive_unindexed(consumer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample<const N: usize>(len: i64) -> HybridMap<i64, i64, N> {
+        (0..len).map(|i| (i, i * 3)).collect()
+    }
+
+    #[test]
+    fn test_par_iter_sum() {
+        let inline = sample::<16>(10);
+        assert!(inline.is_inline());
+        let expected: i64 = inline.values().sum();
+        assert_eq!(inline.par_iter().map(|(_, v)| *v).sum::<i64>(), expected);
+
+        let spilled = sample::<16>(10_000);
+        assert!(spilled.spilled());
+        let expected: i64 = spilled.values().sum();
+        assert_eq!(spilled.par_iter().map(|(_, v)| *v).sum::<i64>(), expected);
+    }
+
+    #[test]
+    fn test_par_iter_mut() {
+        for len in [10, 10_000] {
+            let mut map = sample::<16>(len);
+            map.par_iter_mut().for_each(|(k, v)| *v += k);
+            let expected: i64 = (0..len).map(|i| i * 4).sum();
+            assert_eq!(map.values().sum::<i64>(), expected);
+        }
+    }
+
+    #[test]
+    fn test_into_par_iter() {
+        for len in [10, 10_000] {
+            let map = sample::<16>(len);
+            let expected: i64 = map.iter().map(|(k, v)| k + v).sum();
+            assert_eq!(
+                map.into_par_iter().map(|(k, v)| k + v).sum::<i64>(),
+                expected
+            );
+        }
+    }
+}
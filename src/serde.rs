@@ -0,0 +1,148 @@
+//! Serde support for `HybridMap`, enabled with the `serde` feature.
+use crate::HybridMap;
+use core::{
+    fmt,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+};
+use serde::{
+    de::{MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+// Do not trust the size hint too much, a malicious input could otherwise
+// make us allocate a lot of memory upfront.
+const MAX_PREALLOCATED_ENTRIES: usize = 4096;
+
+impl<K, V, const N: usize, H> Serialize for HybridMap<K, V, N, H>
+where
+    K: Eq + Hash + Serialize,
+    V: Serialize,
+    H: BuildHasher + Clone,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.iter() {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+struct HybridMapVisitor<K, V, const N: usize, H> {
+    // The fn() keeps the visitor Send, Sync and free of drop-check constraints.
+    #[allow(clippy::type_complexity)]
+    marker: PhantomData<fn() -> HybridMap<K, V, N, H>>,
+}
+
+impl<'de, K, V, const N: usize, H> Visitor<'de> for HybridMapVisitor<K, V, N, H>
+where
+    K: Eq + Hash + Deserialize<'de>,
+    V: Deserialize<'de>,
+    H: BuildHasher + Clone + Default,
+{
+    type Value = HybridMap<K, V, N, H>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        // Like FromIterator, start inline as duplicate keys may inflate the size
+        // hint, and reserve the rest of the hint once more than N keys are unique.
+        let mut map = HybridMap::with_hasher(H::default());
+        while let Some((key, value)) = access.next_entry()? {
+            let was_inline = map.is_inline();
+            map.insert(key, value);
+            if was_inline && map.spilled() {
+                map.reserve(
+                    access
+                        .size_hint()
+                        .unwrap_or(0)
+                        .min(MAX_PREALLOCATED_ENTRIES),
+                );
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl<'de, K, V, const N: usize, H> Deserialize<'de> for HybridMap<K, V, N, H>
+where
+    K: Eq + Hash + Deserialize<'de>,
+    V: Deserialize<'de>,
+    H: BuildHasher + Clone + Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(HybridMapVisitor {
+            marker: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InnerContainer;
+
+    #[test]
+    fn test_serde_small_map() {
+        let map = HybridMap::<String, i32, 4>::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(json, r#"{"a":1,"b":2}"#);
+
+        let deserialized: HybridMap<String, i32, 4> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(deserialized.inner, InnerContainer::Vec(..)));
+        assert_eq!(deserialized, map);
+    }
+
+    #[test]
+    fn test_serde_large_map() {
+        let map: HybridMap<String, i32, 4> = (0..10).map(|i| (i.to_string(), i)).collect();
+        let json = serde_json::to_string(&map).unwrap();
+
+        let deserialized: HybridMap<String, i32, 4> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(deserialized.inner, InnerContainer::HashMap(_)));
+        assert_eq!(deserialized.len(), 10);
+        assert_eq!(deserialized, map);
+    }
+
+    #[test]
+    fn test_serde_respects_n() {
+        let json = serde_json::to_string(&(0..10).map(|i| (i.to_string(), i)).collect::<HybridMap<
+            String,
+            i32,
+            16,
+        >>())
+        .unwrap();
+
+        let small: HybridMap<String, i32, 4> = serde_json::from_str(&json).unwrap();
+        assert!(small.spilled());
+        let large: HybridMap<String, i32, 64> = serde_json::from_str(&json).unwrap();
+        assert!(large.is_inline());
+        assert_eq!(small.len(), 10);
+        assert_eq!(large.len(), 10);
+        assert_eq!(large.get("9"), Some(&9));
+    }
+
+    #[test]
+    fn test_serde_size_hint_with_duplicate_keys() {
+        use serde::de::value::{Error, MapDeserializer};
+
+        // The size hint counts the 10 duplicate entries, but they fit inline.
+        let entries = (0..10).map(|i| (i % 2, i));
+        let map = HybridMap::<i32, i32, 4>::deserialize(MapDeserializer::<_, Error>::new(entries))
+            .unwrap();
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&9));
+    }
+
+    #[test]
+    fn test_serde_duplicate_keys() {
+        let map: HybridMap<String, i32, 4> = serde_json::from_str(r#"{"a":1,"a":2}"#).unwrap();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("a"), Some(&2));
+    }
+}
@@ -0,0 +1,288 @@
+//! A `HybridMap` variant keeping its inline vector sorted by key.
+use crate::{DefaultHashBuilder, HybridMap, HybridMapIter, InnerContainer};
+use core::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash},
+};
+
+/// A `HybridMap` that keeps its inline vector sorted by key.
+///
+/// Lookups use a binary search while the map is stored in the vector,
+/// in O(log n) instead of O(n). Insertions and removals shift the following
+/// entries, so this is best suited to small maps with many more lookups than
+/// updates. Once spilled, it behaves like a regular `HybridMap`.
+///
+/// # Examples
+///
+/// ```
+/// use hybridmap::HybridSortedMap;
+///
+/// let mut map = HybridSortedMap::<i32, &str, 8>::new();
+/// map.insert(2, "two");
+/// map.insert(1, "one");
+///
+/// assert_eq!(map.get(&1), Some(&"one"));
+/// assert_eq!(map.iter().next(), Some((&1, &"one")));
+/// ```
+#[derive(Clone, Debug)]
+pub struct HybridSortedMap<K, V, const N: usize = 16, S = DefaultHashBuilder> {
+    map: HybridMap<K, V, N, S>,
+}
+
+// Default trait.
+impl<K, V, const N: usize, S> Default for HybridSortedMap<K, V, N, S>
+where
+    K: Eq + Hash,
+    S: Default,
+{
+    fn default() -> Self {
+        Self {
+            map: HybridMap::default(),
+        }
+    }
+}
+
+// FromIterator trait.
+impl<K, V, const N: usize, S> FromIterator<(K, V)> for HybridSortedMap<K, V, N, S>
+where
+    K: Ord + Hash,
+    S: BuildHasher + Clone + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<K, V, const N: usize> HybridSortedMap<K, V, N, DefaultHashBuilder>
+where
+    K: Ord + Hash,
+{
+    /// Creates an empty `HybridSortedMap`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_hasher(DefaultHashBuilder::default())
+    }
+}
+
+impl<K, V, const N: usize, S> HybridSortedMap<K, V, N, S>
+where
+    K: Ord + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Creates an empty `HybridSortedMap` which will use the given hash
+    /// builder once it switches to a `HashMap`.
+    #[inline]
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            map: HybridMap::with_hasher(hash_builder),
+        }
+    }
+
+    /// Returns the number of elements in the map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns `true` if the map is stored in the sorted inline vector.
+    #[inline]
+    pub fn is_inline(&self) -> bool {
+        self.map.is_inline()
+    }
+
+    /// Returns `true` if the map has switched to a `HashMap`.
+    #[inline]
+    pub fn spilled(&self) -> bool {
+        self.map.spilled()
+    }
+
+    /// Get a reference to the value of the given key.
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + Hash + Eq + ?Sized,
+    {
+        self.map.get_sorted(key)
+    }
+
+    /// Returns `true` if the map contains the given key.
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Get a mutable reference to the value of the given key.
+    #[inline]
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + Hash + Eq + ?Sized,
+    {
+        match &mut self.map.inner {
+            InnerContainer::Vec(vec, _) => vec
+                .binary_search_by(|(k, _)| k.borrow().cmp(key))
+                .ok()
+                .map(|index| &mut vec[index].1),
+            InnerContainer::HashMap(map) => map.get_mut(key),
+        }
+    }
+
+    /// Insert an element into the map, keeping the inline vector sorted.
+    ///
+    /// Returns the previous value if the key was already present.
+    #[inline]
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map.insert_sorted(key, value)
+    }
+
+    /// Remove an entry from the map by its key, returning its value.
+    ///
+    /// Like `HybridMap::remove`, the map switches back to a sorted vector
+    /// when the `HashMap` gets down to the shrink threshold.
+    #[inline]
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + Hash + Eq + ?Sized,
+    {
+        match &mut self.map.inner {
+            InnerContainer::Vec(vec, _) => vec
+                .binary_search_by(|(k, _)| k.borrow().cmp(key))
+                .ok()
+                .map(|index| vec.remove(index).1),
+            InnerContainer::HashMap(_) => {
+                let value = self.map.remove(key);
+                // The map shrank to a vector in the hash map order.
+                if let InnerContainer::Vec(vec, _) = &mut self.map.inner {
+                    vec.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+                }
+                value
+            }
+        }
+    }
+
+    /// Clear the map, removing all elements.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Returns an iterator over the entries of the map.
+    ///
+    /// The entries are in key order while the map is inline.
+    #[inline]
+    pub fn iter(&self) -> HybridMapIter<'_, K, V> {
+        self.map.iter()
+    }
+
+    /// Returns the underlying `HybridMap`.
+    ///
+    /// The inline vector stays sorted until the returned map is modified.
+    #[inline]
+    pub fn into_inner(self) -> HybridMap<K, V, N, S> {
+        self.map
+    }
+}
+
+// A sorted map is a valid HybridMap, the opposite is not true.
+impl<K, V, const N: usize, S> From<HybridSortedMap<K, V, N, S>> for HybridMap<K, V, N, S> {
+    fn from(map: HybridSortedMap<K, V, N, S>) -> Self {
+        map.map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn assert_sorted<const N: usize>(map: &HybridSortedMap<i32, i32, N>) {
+        if let InnerContainer::Vec(vec, _) = &map.map.inner {
+            assert!(vec.windows(2).all(|w| w[0].0 < w[1].0));
+        }
+    }
+
+    #[test]
+    fn test_sorted_insert_get() {
+        let mut map = HybridSortedMap::<i32, i32, 4>::new();
+        assert_eq!(map.insert(3, 30), None);
+        assert_eq!(map.insert(1, 10), None);
+        assert_eq!(map.insert(2, 20), None);
+        assert_eq!(map.insert(1, 11), Some(10));
+        assert!(map.is_inline());
+        assert_sorted(&map);
+        let keys: Vec<_> = map.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![1, 2, 3]);
+        assert_eq!(map.get(&1), Some(&11));
+        assert_eq!(map.get(&4), None);
+
+        *map.get_mut(&2).unwrap() += 1;
+        assert_eq!(map.get(&2), Some(&21));
+
+        map.insert(0, 0);
+        map.insert(5, 50);
+        assert!(map.spilled());
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.get(&5), Some(&50));
+        assert!(map.contains_key(&0));
+    }
+
+    #[test]
+    fn test_sorted_remove_shrinks_sorted() {
+        let mut map: HybridSortedMap<i32, i32, 4> = (0..10).rev().map(|i| (i, i)).collect();
+        assert!(map.spilled());
+        for i in 0..8 {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+        assert!(map.is_inline());
+        assert_sorted(&map);
+        assert_eq!(map.get(&8), Some(&8));
+        assert_eq!(map.get(&9), Some(&9));
+        assert_eq!(map.remove(&8), Some(8));
+        assert_eq!(map.remove(&8), None);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_sorted_random_operations() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut map = HybridSortedMap::<i32, i32, 16>::new();
+        let mut reference = std::collections::BTreeMap::new();
+        for _ in 0..10_000 {
+            let key = rng.gen_range(0..24);
+            if rng.gen_bool(0.5) {
+                assert_eq!(map.insert(key, key * 2), reference.insert(key, key * 2));
+            } else {
+                assert_eq!(map.remove(&key), reference.remove(&key));
+            }
+            assert_sorted(&map);
+            assert_eq!(map.len(), reference.len());
+        }
+        for (key, value) in &reference {
+            assert_eq!(map.get(key), Some(value));
+        }
+    }
+
+    #[test]
+    fn test_sorted_into_inner() {
+        let map: HybridSortedMap<i32, i32, 4> = [(2, 20), (1, 10)].into_iter().collect();
+        let map: HybridMap<i32, i32, 4> = map.into();
+        assert_eq!(map.get(&1), Some(&10));
+        assert_eq!(map.len(), 2);
+    }
+}